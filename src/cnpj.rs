@@ -0,0 +1,112 @@
+//! Brazilian National Register of Legal Entities (CNPJ) numbers.
+
+use std::{
+    fmt,
+    str::{self, FromStr},
+};
+
+use crate::{Error, Result};
+
+const LEN: usize = 14;
+
+/// A validated CNPJ number, which identifies the legal entity holding an
+/// OpenFinance certificate.
+///
+/// Both the traditional numeric format and the alphanumeric format introduced
+/// by Receita Federal, where the first 12 characters may also be uppercase
+/// letters, are supported:
+/// <https://www.gov.br/receitafederal/pt-br/acesso-a-informacao/acoes-e-programas/programas-e-atividades/cnpj-alfanumerico>
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Cnpj([u8; LEN]);
+
+impl Cnpj {
+    /// Get the CNPJ without punctuation, e.g. `43142666000197`.
+    pub fn as_str(&self) -> &str {
+        // Only ASCII characters are ever stored
+        str::from_utf8(&self.0).expect("BUG: non-ASCII CNPJ")
+    }
+
+    /// Get the CNPJ with the usual punctuation, e.g. `43.142.666/0001-97`.
+    pub fn to_formatted_string(&self) -> String {
+        let s = self.as_str();
+
+        format!(
+            "{}.{}.{}/{}-{}",
+            &s[..2],
+            &s[2..5],
+            &s[5..8],
+            &s[8..12],
+            &s[12..]
+        )
+    }
+
+    /// Whether this CNPJ uses the alphanumeric format.
+    pub fn is_alphanumeric(&self) -> bool {
+        self.0.iter().any(u8::is_ascii_alphabetic)
+    }
+}
+
+impl fmt::Display for Cnpj {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parse either with or without punctuation. Letters are case-insensitive.
+impl FromStr for Cnpj {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidCnpj(s.to_owned());
+
+        // Punctuation is optional but must be in the right place if present
+        let raw = if s.len() == 18 {
+            let b = s.as_bytes();
+            if b[2] != b'.' || b[6] != b'.' || b[10] != b'/' || b[15] != b'-' {
+                return Err(invalid());
+            }
+
+            [&s[..2], &s[3..6], &s[7..10], &s[11..15], &s[16..]].concat()
+        } else {
+            s.to_owned()
+        };
+
+        let bytes: [u8; LEN] = raw
+            .to_ascii_uppercase()
+            .into_bytes()
+            .try_into()
+            .map_err(|_| invalid())?;
+        let (base, check_digits) = bytes.split_at(LEN - 2);
+        let is_valid = base
+            .iter()
+            .all(|b| b.is_ascii_digit() || b.is_ascii_uppercase())
+            && check_digits.iter().all(u8::is_ascii_digit)
+            // Repeated digits pass the check digit validation but are not
+            // valid CNPJs
+            && bytes.iter().any(|&b| b != bytes[0])
+            && check_digit(base) == check_digits[0]
+            && check_digit(&bytes[..LEN - 1]) == check_digits[1];
+        if !is_valid {
+            return Err(invalid());
+        }
+
+        Ok(Self(bytes))
+    }
+}
+
+// Compute the modulo 11 check digit of the given characters. The value of
+// each character is its ASCII code minus 48 so that digits keep their value
+// and letters can be used in alphanumeric CNPJs.
+fn check_digit(chars: &[u8]) -> u8 {
+    let sum = chars
+        .iter()
+        .rev()
+        .zip((2..=9).cycle())
+        .map(|(&c, weight)| u32::from(c - b'0') * weight)
+        .sum::<u32>();
+
+    match sum % 11 {
+        0 | 1 => b'0',
+        r => b'0' + (11 - r) as u8,
+    }
+}
@@ -0,0 +1,420 @@
+//! Minimal ASN.1 BER/DER support for the handful of types that show up in
+//! distinguished names.
+//!
+//! Only what is needed to handle names is implemented here: definite
+//! lengths, low tag numbers and primitive string encodings.
+
+use std::result;
+
+use crate::{AttributeTypeAndValue, Error, Oid, RdnType, RelativeDistinguishedName, Result};
+
+pub(crate) const TAG_OID: u8 = 0x06;
+pub(crate) const TAG_SEQUENCE: u8 = 0x30;
+pub(crate) const TAG_SET: u8 = 0x31;
+
+/// An ASN.1 string type that may hold the value of an RDN.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StringType {
+    /// `UTF8String`.
+    Utf8String,
+    /// `NumericString`: digits and spaces only.
+    NumericString,
+    /// `PrintableString`: a restricted subset of ASCII.
+    PrintableString,
+    /// `TeletexString` (a.k.a. `T61String`). Decoded as Latin-1 like
+    /// everybody else does.
+    TeletexString,
+    /// `IA5String`: ASCII.
+    Ia5String,
+    /// `UniversalString`: UTF-32BE.
+    UniversalString,
+    /// `BMPString`: UTF-16BE limited to the basic multilingual plane.
+    BmpString,
+}
+
+impl StringType {
+    /// Get the string type represented by a universal ASN.1 tag.
+    pub(crate) fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0x0c => Some(Self::Utf8String),
+            0x12 => Some(Self::NumericString),
+            0x13 => Some(Self::PrintableString),
+            0x14 => Some(Self::TeletexString),
+            0x16 => Some(Self::Ia5String),
+            0x1c => Some(Self::UniversalString),
+            0x1e => Some(Self::BmpString),
+            _ => None,
+        }
+    }
+
+    /// Get the universal ASN.1 tag of this string type.
+    pub(crate) fn tag(self) -> u8 {
+        match self {
+            Self::Utf8String => 0x0c,
+            Self::NumericString => 0x12,
+            Self::PrintableString => 0x13,
+            Self::TeletexString => 0x14,
+            Self::Ia5String => 0x16,
+            Self::UniversalString => 0x1c,
+            Self::BmpString => 0x1e,
+        }
+    }
+
+    /// Check whether every character of `value` can be represented with
+    /// this string type.
+    pub fn can_represent(self, value: &str) -> bool {
+        value.chars().all(|c| self.can_represent_char(c))
+    }
+
+    fn can_represent_char(self, c: char) -> bool {
+        match self {
+            Self::Utf8String | Self::UniversalString => true,
+            Self::NumericString => c.is_ascii_digit() || c == ' ',
+            Self::PrintableString => c.is_ascii_alphanumeric() || " '()+,-./:=?".contains(c),
+            Self::TeletexString => u32::from(c) <= 0xff,
+            Self::Ia5String => c.is_ascii(),
+            Self::BmpString => u32::from(c) <= 0xffff,
+        }
+    }
+
+    /// Decode the contents octets of a value of this string type.
+    ///
+    /// This is more lenient than [StringType::can_represent]: real
+    /// certificates often carry characters such as `*`, `@` or `_` in a
+    /// `PrintableString`, so only ASCII is required for the restricted
+    /// string types.
+    pub(crate) fn decode(self, contents: &[u8]) -> Result<String> {
+        let value = match self {
+            Self::Utf8String => String::from_utf8(contents.to_vec())?,
+            Self::TeletexString => contents.iter().map(|&b| char::from(b)).collect(),
+            Self::NumericString | Self::PrintableString | Self::Ia5String => {
+                if !contents.is_ascii() {
+                    return Err(Error::InvalidString(self));
+                }
+
+                contents.iter().map(|&b| char::from(b)).collect()
+            }
+            Self::BmpString => {
+                if !contents.len().is_multiple_of(2) {
+                    return Err(Error::InvalidLength);
+                }
+                let units = contents
+                    .chunks_exact(2)
+                    .map(|x| u16::from_be_bytes([x[0], x[1]]));
+
+                let value: String = char::decode_utf16(units)
+                    .collect::<result::Result<_, _>>()
+                    .map_err(|_| Error::InvalidString(self))?;
+                // Surrogate pairs are outside the basic multilingual plane
+                if !self.can_represent(&value) {
+                    return Err(Error::InvalidString(self));
+                }
+
+                value
+            }
+            Self::UniversalString => {
+                if !contents.len().is_multiple_of(4) {
+                    return Err(Error::InvalidLength);
+                }
+
+                contents
+                    .chunks_exact(4)
+                    .map(|x| char::from_u32(u32::from_be_bytes([x[0], x[1], x[2], x[3]])))
+                    .collect::<Option<_>>()
+                    .ok_or(Error::InvalidString(self))?
+            }
+        };
+
+        Ok(value)
+    }
+
+    /// Encode `value` as the contents octets of this string type. Returns
+    /// `None` if the value cannot be represented.
+    pub(crate) fn encode(self, value: &str) -> Option<Vec<u8>> {
+        if !self.can_represent(value) {
+            return None;
+        }
+
+        let contents = match self {
+            Self::Utf8String | Self::NumericString | Self::PrintableString | Self::Ia5String => {
+                value.as_bytes().to_vec()
+            }
+            // Checked above that every character fits in a single byte
+            Self::TeletexString => value.chars().map(|c| u32::from(c) as u8).collect(),
+            Self::BmpString => value.encode_utf16().flat_map(|x| x.to_be_bytes()).collect(),
+            Self::UniversalString => value
+                .chars()
+                .flat_map(|c| u32::from(c).to_be_bytes())
+                .collect(),
+        };
+
+        Some(contents)
+    }
+}
+
+/// Options for encoding a [DistinguishedName](crate::DistinguishedName) as
+/// DER.
+///
+/// The string type of each value is chosen as follows:
+///
+/// 1. The string type the value was originally encoded with, if known and
+///    preserving it was not disabled.
+/// 2. The string type configured for the RDN type with
+///    [DerOptions::string_type].
+/// 3. `PrintableString` for `C`, `jurisdictionCountryName` and
+///    `serialNumber`, `IA5String` for `DC` and `UTF8String` otherwise.
+///
+/// Values that cannot be represented by the chosen string type are encoded
+/// as `UTF8String`s.
+#[derive(Clone, Debug)]
+pub struct DerOptions {
+    preserve_string_types: bool,
+    string_types: Vec<(RdnType, StringType)>,
+}
+
+impl DerOptions {
+    /// Create the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether to keep the string type values were originally encoded with.
+    /// Enabled by default.
+    pub fn preserve_string_types(mut self, preserve: bool) -> Self {
+        self.preserve_string_types = preserve;
+        self
+    }
+
+    /// Use the given string type for values of the given RDN type.
+    pub fn string_type(mut self, ty: RdnType, string_type: StringType) -> Self {
+        self.string_types.retain(|(x, _)| *x != ty);
+        self.string_types.push((ty, string_type));
+        self
+    }
+
+    pub(crate) fn string_type_for(&self, ava: &AttributeTypeAndValue) -> StringType {
+        let ty = ava.ty();
+        let original = ava.string_type().filter(|_| self.preserve_string_types);
+        let configured = || {
+            self.string_types
+                .iter()
+                .find_map(|&(x, string_type)| (x == ty).then_some(string_type))
+        };
+
+        original
+            .or_else(configured)
+            .unwrap_or_else(|| ty.default_string_type())
+    }
+}
+
+impl Default for DerOptions {
+    fn default() -> Self {
+        Self {
+            preserve_string_types: true,
+            string_types: Vec::new(),
+        }
+    }
+}
+
+/// A cursor over a sequence of BER encoded values.
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Read a single tag-length-value triplet, returning the tag and the
+    /// contents octets.
+    pub(crate) fn read(&mut self) -> Result<(u8, &'a [u8])> {
+        let (&tag, rest) = self.data.split_first().ok_or(Error::UnexpectedEof)?;
+        if tag & 0x1f == 0x1f {
+            // High tag numbers are never used by the types we care about
+            return Err(Error::UnexpectedTag(tag));
+        }
+
+        let (&first, mut rest) = rest.split_first().ok_or(Error::UnexpectedEof)?;
+        let len = if first & 0x80 == 0 {
+            usize::from(first)
+        } else {
+            // Long form. Zero length octets would mean an indefinite length,
+            // which is not allowed for primitive encodings and which we don't
+            // need for constructed ones either
+            let n = usize::from(first & 0x7f);
+            if n == 0 || n > 4 || rest.len() < n {
+                return Err(Error::InvalidLength);
+            }
+            let len = rest[..n]
+                .iter()
+                .fold(0usize, |acc, &b| (acc << 8) | usize::from(b));
+            rest = &rest[n..];
+
+            len
+        };
+        if rest.len() < len {
+            return Err(Error::InvalidLength);
+        }
+
+        let (contents, rest) = rest.split_at(len);
+        self.data = rest;
+
+        Ok((tag, contents))
+    }
+
+    /// Read a single tag-length-value triplet with the given tag, returning
+    /// the contents octets.
+    pub(crate) fn read_expected(&mut self, expected: u8) -> Result<&'a [u8]> {
+        let (tag, contents) = self.read()?;
+        if tag != expected {
+            return Err(Error::UnexpectedTag(tag));
+        }
+
+        Ok(contents)
+    }
+
+    /// Read a single tag-length-value triplet with the given tag, returning
+    /// its whole encoding.
+    #[cfg(feature = "x509")]
+    pub(crate) fn read_raw(&mut self, expected: u8) -> Result<&'a [u8]> {
+        let data = self.data;
+        self.read_expected(expected)?;
+
+        Ok(&data[..data.len() - self.data.len()])
+    }
+
+    /// Whether all data was consumed.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Make sure all data was consumed.
+    pub(crate) fn finish(self) -> Result<()> {
+        if self.data.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::TrailingData)
+        }
+    }
+}
+
+/// Decode a single BER encoded string value, as found in the hex form of RDN
+/// values.
+pub(crate) fn decode_string(data: &[u8]) -> Result<(StringType, String)> {
+    let mut reader = Reader::new(data);
+    let (tag, contents) = reader.read()?;
+    reader.finish()?;
+
+    let string_type = StringType::from_tag(tag).ok_or(Error::UnexpectedTag(tag))?;
+    let value = string_type.decode(contents)?;
+
+    Ok((string_type, value))
+}
+
+/// Decode a DER encoded X.501 `Name` into its RDNs, in the order they appear
+/// in the encoding.
+///
+/// ```text
+/// Name ::= SEQUENCE OF RelativeDistinguishedName
+/// RelativeDistinguishedName ::= SET SIZE (1..MAX) OF AttributeTypeAndValue
+/// AttributeTypeAndValue ::= SEQUENCE { type OBJECT IDENTIFIER, value ANY }
+/// ```
+pub(crate) fn decode_name(data: &[u8]) -> Result<Vec<RelativeDistinguishedName>> {
+    let mut reader = Reader::new(data);
+    let name = reader.read_expected(TAG_SEQUENCE)?;
+    reader.finish()?;
+
+    let mut rdns = Vec::new();
+    let mut name = Reader::new(name);
+    while !name.is_empty() {
+        let mut set = Reader::new(name.read_expected(TAG_SET)?);
+        let mut avas = Vec::new();
+        while !set.is_empty() {
+            let mut ava = Reader::new(set.read_expected(TAG_SEQUENCE)?);
+            let ty = RdnType::from_oid(Oid::from_ber(ava.read_expected(TAG_OID)?)?);
+            let (tag, contents) = ava.read()?;
+            ava.finish()?;
+
+            let string_type = StringType::from_tag(tag).ok_or(Error::UnexpectedTag(tag))?;
+            let value = string_type.decode(contents)?;
+            avas.push(AttributeTypeAndValue::new(ty, value).with_string_type(string_type));
+        }
+
+        rdns.push(RelativeDistinguishedName::multi_valued(avas).ok_or(Error::EmptyRdn)?);
+    }
+
+    Ok(rdns)
+}
+
+/// Encode RDNs as a DER encoded X.501 `Name`, in the given order.
+pub(crate) fn encode_name(rdns: &[RelativeDistinguishedName], options: &DerOptions) -> Vec<u8> {
+    let mut name = Vec::new();
+    for rdn in rdns {
+        encode_rdn(&mut name, rdn, |ava| {
+            encode_string(options.string_type_for(ava), ava.value())
+        });
+    }
+
+    let mut res = Vec::with_capacity(name.len() + 6);
+    write_tlv(&mut res, TAG_SEQUENCE, &name);
+
+    res
+}
+
+/// Append the DER encoding of an RDN to `out`, encoding values with the given
+/// function.
+pub(crate) fn encode_rdn(
+    out: &mut Vec<u8>,
+    rdn: &RelativeDistinguishedName,
+    encode_value: impl Fn(&AttributeTypeAndValue) -> Vec<u8>,
+) {
+    let mut avas = rdn
+        .iter()
+        .map(|ava| {
+            let mut contents = Vec::new();
+            write_tlv(&mut contents, TAG_OID, ava.ty().oid().as_ber());
+            contents.extend(encode_value(ava));
+
+            let mut res = Vec::new();
+            write_tlv(&mut res, TAG_SEQUENCE, &contents);
+
+            res
+        })
+        .collect::<Vec<_>>();
+
+    // DER requires the elements of a SET OF to be sorted by their encoding
+    avas.sort();
+    write_tlv(out, TAG_SET, &avas.concat());
+}
+
+/// Encode a string value as DER. Falls back to `UTF8String` if the value
+/// cannot be represented by the requested string type.
+pub(crate) fn encode_string(string_type: StringType, value: &str) -> Vec<u8> {
+    let (string_type, contents) = match string_type.encode(value) {
+        Some(contents) => (string_type, contents),
+        None => (StringType::Utf8String, value.as_bytes().to_vec()),
+    };
+
+    let mut res = Vec::with_capacity(contents.len() + 6);
+    write_tlv(&mut res, string_type.tag(), &contents);
+
+    res
+}
+
+/// Append a DER encoded tag-length-value triplet to `out`.
+pub(crate) fn write_tlv(out: &mut Vec<u8>, tag: u8, contents: &[u8]) {
+    out.push(tag);
+
+    let len = contents.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|&&b| b == 0).count();
+        out.push(0x80 | (bytes.len() - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+
+    out.extend_from_slice(contents);
+}
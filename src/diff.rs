@@ -0,0 +1,218 @@
+//! Explanation of the differences between two DNs.
+
+use std::fmt;
+
+use crate::{DnComparator, RdnComparator};
+
+/// A difference between the RDNs of two compared DNs, the left one being the
+/// DN on which [DnComparator::diff] is called.
+///
+/// Positions are zero-based and counted from the first RDN of the string
+/// representation.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum RdnDiff {
+    /// An RDN of the right DN is missing from the left DN.
+    MissingOnLeft {
+        /// The missing RDN.
+        rdn: RdnComparator,
+        /// The position of the RDN in the right DN.
+        position: usize,
+    },
+    /// An RDN of the left DN is missing from the right DN.
+    MissingOnRight {
+        /// The missing RDN.
+        rdn: RdnComparator,
+        /// The position of the RDN in the left DN.
+        position: usize,
+    },
+    /// Both DNs have an RDN with the same attribute types but different
+    /// values after transformation.
+    ValueDiffers {
+        /// The RDN of the left DN.
+        left: RdnComparator,
+        /// The RDN of the right DN.
+        right: RdnComparator,
+        /// The position of the RDN in the left DN.
+        left_position: usize,
+        /// The position of the RDN in the right DN.
+        right_position: usize,
+    },
+    /// Both DNs have the same RDN but in different orders.
+    OrderDiffers {
+        /// The RDN present in both DNs.
+        rdn: RdnComparator,
+        /// The position of the RDN in the left DN.
+        left_position: usize,
+        /// The position of the RDN in the right DN.
+        right_position: usize,
+    },
+}
+
+impl fmt::Display for RdnDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingOnLeft { rdn, position } => {
+                write!(f, "`{rdn}` at position {position} is missing on the left")
+            }
+            Self::MissingOnRight { rdn, position } => {
+                write!(f, "`{rdn}` at position {position} is missing on the right")
+            }
+            Self::ValueDiffers {
+                left,
+                right,
+                left_position,
+                right_position,
+            } => write!(
+                f,
+                "`{left}` at position {left_position} differs from `{right}` at position \
+                 {right_position}"
+            ),
+            Self::OrderDiffers {
+                rdn,
+                left_position,
+                right_position,
+            } => write!(
+                f,
+                "`{rdn}` is at position {left_position} on the left but at position \
+                 {right_position} on the right"
+            ),
+        }
+    }
+}
+
+impl DnComparator {
+    /// Explain why this DN differs from another one. An empty result means
+    /// that both DNs are equal.
+    ///
+    /// RDNs are first matched in order and then the remaining ones are
+    /// paired either when they are equal but out of order or when they have
+    /// the same attribute types, in which case their values differ.
+    pub fn diff(&self, other: &Self) -> Vec<RdnDiff> {
+        // Work with the string order, which is what users see
+        let left = self.rdns.iter().rev().collect::<Vec<_>>();
+        let right = other.rdns.iter().rev().collect::<Vec<_>>();
+
+        let (left_rest, right_rest) = unmatched(&left, &right);
+
+        explain(&left, &right, left_rest, right_rest)
+    }
+
+    // Like `diff` but ignoring the order of the RDNs, so that only the RDNs
+    // present in one DN more times than in the other are reported.
+    pub(crate) fn diff_unordered(&self, other: &Self) -> Vec<RdnDiff> {
+        let left = self.rdns.iter().rev().collect::<Vec<_>>();
+        let right = other.rdns.iter().rev().collect::<Vec<_>>();
+
+        let mut right_rest = (0..right.len()).collect::<Vec<_>>();
+        let left_rest = (0..left.len())
+            .filter(|&i| take(&mut right_rest, |j| left[i] == right[j]).is_none())
+            .collect();
+
+        explain(&left, &right, left_rest, right_rest)
+    }
+}
+
+// Explain the differences between the RDNs that could not be matched.
+fn explain(
+    left: &[&RdnComparator],
+    right: &[&RdnComparator],
+    mut left_rest: Vec<usize>,
+    mut right_rest: Vec<usize>,
+) -> Vec<RdnDiff> {
+    let mut diffs = Vec::new();
+
+    // Equal RDNs that could not be matched in order are out of order
+    left_rest.retain(|&i| {
+        let Some(j) = take(&mut right_rest, |j| left[i] == right[j]) else {
+            return true;
+        };
+        diffs.push(RdnDiff::OrderDiffers {
+            rdn: left[i].clone(),
+            left_position: i,
+            right_position: j,
+        });
+
+        false
+    });
+
+    // RDNs of the same types have different values
+    left_rest.retain(|&i| {
+        let Some(j) = take(&mut right_rest, |j| same_types(left[i], right[j])) else {
+            return true;
+        };
+        diffs.push(RdnDiff::ValueDiffers {
+            left: left[i].clone(),
+            right: right[j].clone(),
+            left_position: i,
+            right_position: j,
+        });
+
+        false
+    });
+
+    diffs.extend(left_rest.into_iter().map(|i| RdnDiff::MissingOnRight {
+        rdn: left[i].clone(),
+        position: i,
+    }));
+    diffs.extend(right_rest.into_iter().map(|j| RdnDiff::MissingOnLeft {
+        rdn: right[j].clone(),
+        position: j,
+    }));
+    diffs.sort_by_key(|x| match x {
+        RdnDiff::MissingOnRight { position, .. } => (*position, 0),
+        RdnDiff::ValueDiffers { left_position, .. }
+        | RdnDiff::OrderDiffers { left_position, .. } => (*left_position, 0),
+        RdnDiff::MissingOnLeft { position, .. } => (*position, 1),
+    });
+
+    diffs
+}
+
+// Find the positions of the RDNs that are not part of the longest common
+// subsequence of both DNs.
+fn unmatched(left: &[&RdnComparator], right: &[&RdnComparator]) -> (Vec<usize>, Vec<usize>) {
+    // `lcs[i][j]` is the length of the LCS of `left[i..]` and `right[j..]`
+    let mut lcs = vec![vec![0; right.len() + 1]; left.len() + 1];
+    for i in (0..left.len()).rev() {
+        for j in (0..right.len()).rev() {
+            lcs[i][j] = if left[i] == right[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let (mut left_rest, mut right_rest) = (Vec::new(), Vec::new());
+    while i < left.len() && j < right.len() {
+        if left[i] == right[j] {
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            left_rest.push(i);
+            i += 1;
+        } else {
+            right_rest.push(j);
+            j += 1;
+        }
+    }
+    left_rest.extend(i..left.len());
+    right_rest.extend(j..right.len());
+
+    (left_rest, right_rest)
+}
+
+// Remove and return the first position matching the predicate.
+fn take(positions: &mut Vec<usize>, f: impl Fn(usize) -> bool) -> Option<usize> {
+    let idx = positions.iter().position(|&x| f(x))?;
+
+    Some(positions.remove(idx))
+}
+
+fn same_types(left: &RdnComparator, right: &RdnComparator) -> bool {
+    left.avas
+        .iter()
+        .map(|x| x.ty)
+        .eq(right.avas.iter().map(|x| x.ty))
+}
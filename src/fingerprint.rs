@@ -0,0 +1,48 @@
+//! Stable fingerprints of normalized DNs.
+
+use std::fmt;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use sha2::{Digest, Sha256};
+
+use crate::DnComparator;
+
+/// A SHA-256 digest identifying a [DnComparator], see
+/// [DnComparator::fingerprint].
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Fingerprint([u8; 32]);
+
+impl Fingerprint {
+    /// Get the raw digest.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Encode the digest as lowercase hex.
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    /// Encode the digest as unpadded base64url.
+    pub fn to_base64url(&self) -> String {
+        URL_SAFE_NO_PAD.encode(self.0)
+    }
+}
+
+/// Format as lowercase hex.
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl DnComparator {
+    /// Compute the SHA-256 digest of the UTF-8 encoded canonical string of
+    /// this comparator, see [Self::to_canonical_string]. Since the canonical
+    /// format never changes, fingerprints are stable across processes and
+    /// versions of this crate, though they do depend on the
+    /// [crate::MatchingProfile] used to create the comparator.
+    pub fn fingerprint(&self) -> Fingerprint {
+        Fingerprint(Sha256::digest(self.to_canonical_string()).into())
+    }
+}
@@ -3,21 +3,51 @@
 
 use std::{
     borrow::Cow,
-    result,
+    fmt, mem, result,
     str::{self, FromStr, Utf8Error},
     string::FromUtf8Error,
 };
 
 use derive_more::{Display, Error, From};
 
+pub use cnpj::Cnpj;
+pub use der::{DerOptions, StringType};
+pub use diff::RdnDiff;
+pub use fingerprint::Fingerprint;
+pub use matching::{MatchingProfile, MatchingRule};
+pub use oid::Oid;
+pub use openfinance::OpenFinanceSubject;
+pub use openssl::OpensslFormat;
+pub use pattern::{DnCaptures, DnPattern};
+pub use verify::{RdnMismatch, SubjectDnVerifier, Verification};
+
+mod cnpj;
+mod der;
+mod diff;
+mod fingerprint;
+mod matching;
+mod oid;
+mod openfinance;
+mod openssl;
+mod pattern;
+mod prep;
+#[cfg(feature = "serde")]
+pub mod serde;
 #[cfg(test)]
 mod test;
+mod verify;
+#[cfg(feature = "x509")]
+mod x509;
 
 // List of symbols that must be escaped with a backslash
 const ESCAPABLE_SYMBOLS: [char; 10] = [' ', '"', '#', '+', ',', ';', '<', '=', '>', '\\'];
 
 /// Possible errors when parsing distinguished names.
+///
+/// Some variants only exist with certain features enabled, so this enum is
+/// non-exhaustive to keep features additive.
 #[derive(Debug, Display, Error, From)]
+#[non_exhaustive]
 pub enum Error {
     /// Could not decode a hex string.
     Hex(hex::FromHexError),
@@ -36,13 +66,78 @@ pub enum Error {
     /// String ended unexpectedly.
     #[display(fmt = "unexpected EOF")]
     UnexpectedEof,
-    /// We don't support nor need to support multi-value RDNs.
+    /// Found a multi-valued RDN where they are not allowed.
     #[display(fmt = "multi-value RDNs are not supported")]
     UnsupportedMultiValueRdns,
     /// Found a non-UTF-8 string.
     FromUtf8(FromUtf8Error),
     /// Found a non-UTF-8 string.
     Utf8(Utf8Error),
+    /// Found an invalid object identifier.
+    #[display(fmt = "invalid OID: {_0}")]
+    #[from(ignore)]
+    InvalidOid(#[error(not(source))] String),
+    /// Found an ASN.1 tag that is not valid in this position.
+    #[display(fmt = "unexpected ASN.1 tag: {_0:#04x}")]
+    #[from(ignore)]
+    UnexpectedTag(#[error(not(source))] u8),
+    /// Found an ASN.1 length that is malformed or out of bounds.
+    #[display(fmt = "invalid ASN.1 length")]
+    InvalidLength,
+    /// Found data after the end of an ASN.1 value.
+    #[display(fmt = "trailing data after ASN.1 value")]
+    TrailingData,
+    /// Found an empty or repeated capture name in a DN pattern.
+    #[display(fmt = "invalid capture name: {_0:?}")]
+    #[from(ignore)]
+    InvalidCapture(#[error(not(source))] String),
+    /// Found a string that is not in the canonical form of a comparator.
+    #[display(fmt = "non-canonical string: {_0}")]
+    #[from(ignore)]
+    NonCanonicalString(#[error(not(source))] String),
+    /// Found an invalid CNPJ.
+    #[display(fmt = "invalid CNPJ: {_0}")]
+    #[from(ignore)]
+    InvalidCnpj(#[error(not(source))] String),
+    /// A required attribute is missing.
+    #[display(fmt = "missing attribute: {_0:?}")]
+    #[from(ignore)]
+    MissingAttribute(#[error(not(source))] RdnType),
+    /// An attribute that may only appear once is repeated.
+    #[display(fmt = "duplicate attribute: {_0:?}")]
+    #[from(ignore)]
+    DuplicateAttribute(#[error(not(source))] RdnType),
+    /// Found an RDN without any attributes.
+    #[display(fmt = "empty RDN")]
+    EmptyRdn,
+    /// Found contents that are not valid for the ASN.1 string type.
+    #[display(fmt = "invalid contents for {_0:?}")]
+    #[from(ignore)]
+    InvalidString(#[error(not(source))] StringType),
+    /// The string to parse is longer than allowed.
+    #[display(fmt = "input longer than {_0} bytes")]
+    #[from(ignore)]
+    InputTooLong(#[error(not(source))] usize),
+    /// The DN has more RDNs than allowed.
+    #[display(fmt = "more than {_0} RDNs")]
+    #[from(ignore)]
+    TooManyRdns(#[error(not(source))] usize),
+    /// A hex encoded value is longer than allowed.
+    #[display(fmt = "hex encoded value longer than {_0} bytes")]
+    #[from(ignore)]
+    HexValueTooLong(#[error(not(source))] usize),
+    /// Found a string that does not conform to RFC4514 in strict mode.
+    #[display(fmt = "invalid syntax at byte {position}: {message}")]
+    #[from(ignore)]
+    Syntax { position: usize, message: String },
+    /// Could not decode a PEM file.
+    #[cfg(feature = "x509")]
+    Pem(pem::PemError),
+    /// Found a PEM file that does not contain a certificate.
+    #[cfg(feature = "x509")]
+    #[display(fmt = "unexpected PEM label: {_0}")]
+    #[from(ignore)]
+    UnexpectedPemLabel(#[error(not(source))] String),
 }
 
 /// Parsing result type.
@@ -58,11 +153,39 @@ pub struct DistinguishedName {
 }
 
 impl DistinguishedName {
-    /// Find the value of the first occurence of the given RDN type.
+    /// Parse from a DER encoded X.501 `Name`, as found in the subject and
+    /// issuer fields of X.509 certificates.
+    pub fn from_der(der: &[u8]) -> Result<Self> {
+        let rdns = der::decode_name(der)?;
+
+        Ok(Self { rdns })
+    }
+
+    /// Serialize into a DER encoded X.501 `Name` using the default
+    /// [DerOptions].
+    pub fn to_der(&self) -> Vec<u8> {
+        self.to_der_with(&DerOptions::default())
+    }
+
+    /// Serialize into a DER encoded X.501 `Name`.
+    pub fn to_der_with(&self, options: &DerOptions) -> Vec<u8> {
+        der::encode_name(&self.rdns, options)
+    }
+
+    /// Find the value of the first occurence of the given RDN type, looking
+    /// into every attribute of multi-valued RDNs. Types are compared by OID,
+    /// e.g. `OU` also finds `organizationalUnitName`.
     pub fn find(&self, ty: RdnType) -> Option<&str> {
+        let ty = ty.normalize();
+        self.find_by(|x| x.normalize() == ty)
+    }
+
+    // Find the value of the first attribute whose type matches.
+    fn find_by(&self, f: impl Fn(RdnType) -> bool) -> Option<&str> {
         self.rdns
             .iter()
-            .find_map(|x| if x.ty() == ty { Some(x.value()) } else { None })
+            .flat_map(RelativeDistinguishedName::iter)
+            .find_map(|x| if f(x.ty()) { Some(x.value()) } else { None })
     }
 
     /// Returns an iterator over all RDNs of this DN.
@@ -70,16 +193,133 @@ impl DistinguishedName {
         self.rdns.iter()
     }
 
+    /// Create a builder to construct a DN one RDN at a time, in string
+    /// order, e.g. `DistinguishedName::builder().cn("app").c("BR").build()`.
+    pub fn builder() -> DnBuilder {
+        DnBuilder::default()
+    }
+
+    /// Append an RDN after the last RDN of the string representation, i.e.
+    /// as the new root of this DN.
+    pub fn push(&mut self, rdn: RelativeDistinguishedName) -> Result<()> {
+        self.insert(self.rdns.len(), rdn)
+    }
+
+    /// Insert an RDN at the given position, counted from the first RDN of
+    /// the string representation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of RDNs.
+    pub fn insert(&mut self, index: usize, rdn: RelativeDistinguishedName) -> Result<()> {
+        assert!(index <= self.rdns.len(), "RDN index out of bounds");
+        rdn.validate()?;
+        self.rdns.insert(self.rdns.len() - index, rdn);
+
+        Ok(())
+    }
+
+    /// Remove and return the RDN at the given position, counted from the
+    /// first RDN of the string representation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> RelativeDistinguishedName {
+        assert!(index < self.rdns.len(), "RDN index out of bounds");
+        self.rdns.remove(self.rdns.len() - 1 - index)
+    }
+
+    /// Replace the value of the attribute found by [Self::find]. The original
+    /// string type is kept if it can represent the new value.
+    pub fn set(&mut self, ty: RdnType, value: impl Into<String>) -> Result<()> {
+        let value = value.into();
+        ty.validate(&value)?;
+
+        let ava = self
+            .rdns
+            .iter_mut()
+            .flat_map(|x| x.avas.iter_mut())
+            .find(|x| x.ty.normalize() == ty.normalize())
+            .ok_or(Error::MissingAttribute(ty))?;
+        if !ava.string_type.is_some_and(|x| x.can_represent(&value)) {
+            ava.string_type = None;
+        }
+        ava.value = value;
+
+        Ok(())
+    }
+
+    /// Keep only the RDNs for which the predicate returns `true`.
+    pub fn retain(&mut self, f: impl FnMut(&RelativeDistinguishedName) -> bool) {
+        self.rdns.retain(f);
+    }
+
+    /// Get the DN of the parent entry, i.e. this DN without its first RDN,
+    /// or [None] for the empty DN.
+    pub fn parent(&self) -> Option<Self> {
+        let (_, rdns) = self.rdns.split_last()?;
+
+        Some(Self {
+            rdns: rdns.to_vec(),
+        })
+    }
+
+    /// Whether this DN is in the subtree of the given base DN, i.e. whether
+    /// the base DN is a suffix of this one. Every DN is a descendant of
+    /// itself.
+    pub fn is_descendant_of(&self, base: &Self) -> Result<bool> {
+        Ok(self.strip_suffix(base)?.is_some())
+    }
+
+    /// Get the RDNs of this DN relative to the given base DN, e.g.
+    /// `CN=foo,O=bar,C=BR` relative to `O=bar,C=BR` is `CN=foo`. Returns
+    /// [None] if this DN is not a descendant of the base DN.
+    pub fn strip_suffix(&self, base: &Self) -> Result<Option<Self>> {
+        if base.rdns.len() > self.rdns.len() || self.common_suffix_len(base)? < base.rdns.len() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            rdns: self.rdns[base.rdns.len()..].to_vec(),
+        }))
+    }
+
+    /// Get the longest suffix shared by this DN and another one, i.e. their
+    /// closest common ancestor. The RDNs are taken from this DN.
+    pub fn common_suffix(&self, other: &Self) -> Result<Self> {
+        let len = self.common_suffix_len(other)?;
+
+        Ok(Self {
+            rdns: self.rdns[..len].to_vec(),
+        })
+    }
+
+    // Count the RDNs shared by the suffixes of both DNs, comparing them after
+    // transformation.
+    fn common_suffix_len(&self, other: &Self) -> Result<usize> {
+        // RDNs are stored starting from the root so suffixes are at the start
+        let mut len = 0;
+        for (a, b) in self.rdns.iter().zip(&other.rdns) {
+            if RdnComparator::new(a)? != RdnComparator::new(b)? {
+                break;
+            }
+            len += 1;
+        }
+
+        Ok(len)
+    }
+
     /// Get the organization ID of this certificate. The way organization IDs
     /// are specified in OpenFinance certificates is a special kind of mess so
     /// we need a specific function for this.
-    pub fn organization_id(&self) -> Result<Option<Cow<str>>> {
+    pub fn organization_id(&self) -> Result<Option<Cow<'_, str>>> {
         // For newer certificates, the organization ID should be the value of
         // `OU` and the spec also leaves open the possibility of it being the
         // value of `organizationalUnitName`
         let org_id = self
-            .find(RdnType::Ou)
-            .or_else(|| self.find(RdnType::OrganizationalUnitName));
+            .find_by(|x| x == RdnType::Ou)
+            .or_else(|| self.find_by(|x| x == RdnType::OrganizationalUnitName));
         if let Some(org_id) = org_id {
             return Ok(Some(org_id.into()));
         }
@@ -94,6 +334,12 @@ impl DistinguishedName {
         Ok(Some(extract_organization_id(&org_id)?.into()))
     }
 
+    /// Get the CNPJ of the legal entity holding this certificate, which is
+    /// the value of `serialNumber`.
+    pub fn cnpj(&self) -> Result<Option<Cnpj>> {
+        self.find(RdnType::SerialNumber).map(str::parse).transpose()
+    }
+
     /// Create a comparator for this DN.
     /// [RFC4518](https://datatracker.ietf.org/doc/html/rfc451) requires that
     /// DNs be transformed before comparison, which is implemented by this
@@ -102,6 +348,12 @@ impl DistinguishedName {
         DnComparator::new(self)
     }
 
+    /// Create a comparator for this DN using the given set of matching
+    /// rules.
+    pub fn comparator_with(&self, profile: MatchingProfile) -> Result<DnComparator> {
+        DnComparator::with_profile(self, profile)
+    }
+
     /// Serialize into the OpenFinance variant string format:
     /// <https://openfinancebrasil.atlassian.net/wiki/spaces/OF/pages/240649661/EN+Open+Finance+Brasil+Financial-grade+API+Dynamic+Client+Registration+1.0+Implementers+Draft+3#7.1.2.-Certificate-Distinguished-Name-Parsing>.
     pub fn to_of_string(&self) -> String {
@@ -111,23 +363,21 @@ impl DistinguishedName {
                 res.push(',');
             }
 
-            let ty = rdn.ty();
-            let value = rdn.value();
-            res += ty.as_of_str();
-            res.push('=');
-            if ty.of_encodes_as_hex() {
-                res.push('#');
-                res += &hex::encode(value);
-            } else {
-                res.reserve(value.len());
-                for c in value.chars() {
-                    if ESCAPABLE_SYMBOLS.contains(&c) {
-                        // Note: for simplicity we'll be escaping everything
-                        // we can unconditionally even when this is not
-                        // necesary
-                        res.push('\\');
-                    }
-                    res.push(c);
+            for (j, ava) in rdn.iter().enumerate() {
+                if j > 0 {
+                    res.push('+');
+                }
+
+                let ty = ava.ty();
+                let value = ava.value();
+                res += &ty.as_of_str();
+                res.push('=');
+                if ty.of_encodes_as_hex() {
+                    let string_type = DerOptions::default().string_type_for(ava);
+                    res.push('#');
+                    res += &hex::encode(der::encode_string(string_type, value));
+                } else {
+                    push_escaped(&mut res, value);
                 }
             }
         }
@@ -136,122 +386,537 @@ impl DistinguishedName {
     }
 }
 
+/// A builder of [DistinguishedName]s. RDNs are added in string order, so the
+/// first one added is the leftmost RDN of the string representation.
+#[derive(Clone, Debug, Default)]
+pub struct DnBuilder {
+    rdns: Vec<RelativeDistinguishedName>,
+}
+
+impl DnBuilder {
+    /// Add an RDN, possibly multi-valued.
+    pub fn rdn(mut self, rdn: RelativeDistinguishedName) -> Self {
+        self.rdns.push(rdn);
+        self
+    }
+
+    /// Add an RDN with a single attribute.
+    pub fn attribute(self, ty: RdnType, value: impl Into<String>) -> Self {
+        self.rdn(RelativeDistinguishedName::new(ty, value.into()))
+    }
+
+    /// Add a common name (`CN`).
+    pub fn cn(self, value: impl Into<String>) -> Self {
+        self.attribute(RdnType::Cn, value)
+    }
+
+    /// Add a locality name (`L`).
+    pub fn l(self, value: impl Into<String>) -> Self {
+        self.attribute(RdnType::L, value)
+    }
+
+    /// Add a state or province name (`ST`).
+    pub fn st(self, value: impl Into<String>) -> Self {
+        self.attribute(RdnType::St, value)
+    }
+
+    /// Add an organization name (`O`).
+    pub fn o(self, value: impl Into<String>) -> Self {
+        self.attribute(RdnType::O, value)
+    }
+
+    /// Add an organizational unit name (`OU`).
+    pub fn ou(self, value: impl Into<String>) -> Self {
+        self.attribute(RdnType::Ou, value)
+    }
+
+    /// Add a country name (`C`).
+    pub fn c(self, value: impl Into<String>) -> Self {
+        self.attribute(RdnType::C, value)
+    }
+
+    /// Add a street address (`street`).
+    pub fn street(self, value: impl Into<String>) -> Self {
+        self.attribute(RdnType::Street, value)
+    }
+
+    /// Add a domain component (`DC`).
+    pub fn dc(self, value: impl Into<String>) -> Self {
+        self.attribute(RdnType::Dc, value)
+    }
+
+    /// Add a user ID (`UID`).
+    pub fn uid(self, value: impl Into<String>) -> Self {
+        self.attribute(RdnType::Uid, value)
+    }
+
+    /// Add a type of business category (`businessCategory`).
+    pub fn business_category(self, value: impl Into<String>) -> Self {
+        self.attribute(RdnType::BusinessCategory, value)
+    }
+
+    /// Add a jurisdiction country name (`jurisdictionCountryName`).
+    pub fn jurisdiction_country_name(self, value: impl Into<String>) -> Self {
+        self.attribute(RdnType::JurisdictionCountryName, value)
+    }
+
+    /// Add a serial number (`serialNumber`).
+    pub fn serial_number(self, value: impl Into<String>) -> Self {
+        self.attribute(RdnType::SerialNumber, value)
+    }
+
+    /// Add an organization identifier (`organizationIdentifier`).
+    pub fn organization_identifier(self, value: impl Into<String>) -> Self {
+        self.attribute(RdnType::OrganizationIdentifier, value)
+    }
+
+    /// Add an organizational unit name in OID form (`2.5.4.11`).
+    pub fn organizational_unit_name(self, value: impl Into<String>) -> Self {
+        self.attribute(RdnType::OrganizationalUnitName, value)
+    }
+
+    /// Build the DN, validating the value of every attribute.
+    pub fn build(self) -> Result<DistinguishedName> {
+        let mut dn = DistinguishedName { rdns: Vec::new() };
+        for rdn in self.rdns {
+            dn.push(rdn)?;
+        }
+
+        Ok(dn)
+    }
+}
+
 /// Parse from the canonical string format:
 /// <https://datatracker.ietf.org/doc/html/rfc4514>.
 impl FromStr for DistinguishedName {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        // This format is faily straightforward and so the parser is
-        // implemented manually. Parser crates wouldn't help by much.
-        let mut rdns = Vec::new();
-        let mut acc = Vec::new();
-        let mut escaping = Escaping::None;
-        let mut value_is_hex = false;
-        let mut ty = None::<RdnType>;
-        let chars = s.bytes().map(ParseItem::from).chain([ParseItem::Eof]);
-        for c in chars {
-            if escaping.is_pending() {
-                let ParseItem::Byte(c) = c else {
-                    // Cannot end a DN with a backslash
-                    return Err(Error::UnexpectedEof);
-                };
-                if let Some(escaped) = escaping.consume(c)? {
-                    acc.push(escaped);
-                }
+        parse(s, &ParserOptions::default())
+    }
+}
 
-                continue;
-            }
+impl DistinguishedName {
+    /// Parse from the string format using the given options. With the
+    /// default options this is the same as [DistinguishedName::from_str].
+    pub fn parse_with(s: &str, options: &ParserOptions) -> Result<Self> {
+        parse(s, options)
+    }
 
-            match c {
-                // A DN is a list of RDNs separated by commas
-                ParseItem::Byte(b',') | ParseItem::Eof => {
-                    let value = str::from_utf8(&acc)?.trim();
-                    if value.is_empty() {
-                        if c.is_eof() && ty.is_none() {
-                            // EOF and the DN is complete
-                            break;
-                        } else {
-                            // We already parsed a type but this RDN is
-                            // missing a value
-                            return if c.is_eof() {
-                                Err(Error::UnexpectedEof)
-                            } else {
-                                Err(Error::UnexpectedCharacter(','))
-                            };
-                        }
-                    }
+    /// Parse from the legacy string formats of RFC2253 and RFC1779, as still
+    /// emitted by some older tools.
+    ///
+    /// On top of the RFC4514 format, this accepts semicolons as RDN
+    /// separators, values between double quotes, such as `O="Foo, Inc."`,
+    /// and attribute types written as `OID.2.5.4.3`.
+    pub fn parse_legacy(s: &str) -> Result<Self> {
+        parse(s, &ParserOptions::new().legacy(true))
+    }
+}
 
-                    // If we're ending the definition of this RDN then we must
-                    // already have parsed an RDN type
-                    let rdn_type = ty.ok_or_else(|| {
-                        if c.is_eof() {
-                            Error::UnexpectedEof
-                        } else {
-                            Error::UnexpectedCharacter(',')
-                        }
-                    })?;
-                    ty = None;
+/// Options for parsing [DistinguishedName]s from strings.
+///
+/// The defaults accept everything [DistinguishedName::from_str] does, without
+/// any limits. Untrusted input should at least be subject to a maximum
+/// length.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct ParserOptions {
+    max_length: Option<usize>,
+    max_rdns: Option<usize>,
+    max_hex_value_length: Option<usize>,
+    allow_unknown_types: bool,
+    allow_multi_valued_rdns: bool,
+    allow_empty_values: bool,
+    strict: bool,
+    legacy: bool,
+}
+
+impl ParserOptions {
+    /// Create the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reject strings longer than the given number of bytes.
+    pub fn max_length(mut self, max: usize) -> Self {
+        self.max_length = Some(max);
+        self
+    }
+
+    /// Reject DNs with more than the given number of RDNs.
+    pub fn max_rdns(mut self, max: usize) -> Self {
+        self.max_rdns = Some(max);
+        self
+    }
+
+    /// Reject hex encoded values, such as `#0c03666f6f`, longer than the
+    /// given number of bytes once decoded.
+    pub fn max_hex_value_length(mut self, max: usize) -> Self {
+        self.max_hex_value_length = Some(max);
+        self
+    }
+
+    /// Whether to accept attribute types other than the ones with a name in
+    /// [RdnType]. Enabled by default.
+    pub fn allow_unknown_types(mut self, allow: bool) -> Self {
+        self.allow_unknown_types = allow;
+        self
+    }
+
+    /// Whether to accept RDNs with more than one attribute. Enabled by
+    /// default.
+    pub fn allow_multi_valued_rdns(mut self, allow: bool) -> Self {
+        self.allow_multi_valued_rdns = allow;
+        self
+    }
+
+    /// Whether to accept attributes with empty values, such as `CN=`.
+    /// Disabled by default.
+    pub fn allow_empty_values(mut self, allow: bool) -> Self {
+        self.allow_empty_values = allow;
+        self
+    }
+
+    /// Whether to reject any string that does not conform to RFC4514 with an
+    /// [Error::Syntax] pointing at the problem, instead of accepting and
+    /// normalizing it. Disabled by default.
+    ///
+    /// This rejects characters that must be escaped, namely `"`, `;`, `<`,
+    /// `>` and NUL, unescaped leading and trailing spaces in values,
+    /// whitespace around separators and equals signs, malformed attribute
+    /// types and hex values, and empty values unless
+    /// [ParserOptions::allow_empty_values] is set. Escaped leading and
+    /// trailing spaces are kept and equals signs are allowed in values.
+    /// Takes precedence over [ParserOptions::legacy].
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Whether to accept the legacy syntax described in
+    /// [DistinguishedName::parse_legacy]. Disabled by default.
+    pub fn legacy(mut self, legacy: bool) -> Self {
+        self.legacy = legacy;
+        self
+    }
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            max_length: None,
+            max_rdns: None,
+            max_hex_value_length: None,
+            allow_unknown_types: true,
+            allow_multi_valued_rdns: true,
+            allow_empty_values: false,
+            strict: false,
+            legacy: false,
+        }
+    }
+}
+
+fn parse(s: &str, options: &ParserOptions) -> Result<DistinguishedName> {
+    if let Some(max) = options.max_length.filter(|&max| s.len() > max) {
+        return Err(Error::InputTooLong(max));
+    }
+    if options.strict {
+        validate_strict(s, options.allow_empty_values)?;
+    }
+
+    // This format is faily straightforward and so the parser is
+    // implemented manually. Parser crates wouldn't help by much.
+    let legacy = options.legacy && !options.strict;
+    let mut rdns = Vec::new();
+    let mut avas = Vec::new();
+    let mut acc = Vec::new();
+    let mut escaping = Escaping::None;
+    let mut quoting = Quoting::None;
+    let mut value_is_hex = false;
+    let mut ty = None::<RdnType>;
+    let chars = s.bytes().map(ParseItem::from).chain([ParseItem::Eof]);
+    for c in chars {
+        if escaping.is_pending() {
+            let ParseItem::Byte(c) = c else {
+                // Cannot end a DN with a backslash
+                return Err(Error::UnexpectedEof);
+            };
+            if let Some(escaped) = escaping.consume(c)? {
+                acc.push(escaped);
+            }
+
+            continue;
+        }
 
-                    // Decode the value. This may be a hex encoded string
-                    let rdn_value = if value_is_hex {
-                        value_is_hex = false;
-                        let value = hex::decode(value)?;
+        // Quoted values are literal except for escape sequences and may
+        // only be followed by whitespace
+        match (quoting, c) {
+            (Quoting::Open, ParseItem::Byte(b'"')) => {
+                quoting = Quoting::Closed;
+                continue;
+            }
+            (Quoting::Open, ParseItem::Byte(b'\\')) => {
+                escaping = Escaping::Started;
+                continue;
+            }
+            (Quoting::Open, ParseItem::Byte(c)) => {
+                acc.push(c);
+                continue;
+            }
+            (Quoting::Open, ParseItem::Eof) => return Err(Error::UnexpectedEof),
+            (Quoting::Closed, ParseItem::Byte(c)) if c.is_ascii_whitespace() => continue,
+            (Quoting::Closed, ParseItem::Byte(c)) if !matches!(c, b',' | b'+' | b';') => {
+                return Err(Error::UnexpectedCharacter(c.into()));
+            }
+            _ => {}
+        }
 
-                        String::from_utf8(value)?
-                    } else {
-                        value.to_owned()
-                    };
-                    acc.clear();
+        match c {
+            // Semicolons are only separators in the legacy format
+            ParseItem::Byte(b';') if !legacy => acc.push(b';'),
+            // A DN is a list of RDNs separated by commas, or semicolons in
+            // the legacy format, and an RDN is a list of attributes
+            // separated by plus signs
+            ParseItem::Byte(b',' | b'+' | b';') | ParseItem::Eof => {
+                let value = str::from_utf8(&acc)?;
+                let value = match mem::replace(&mut quoting, Quoting::None) {
+                    Quoting::Closed => value,
+                    // Strings were validated so any remaining leading and
+                    // trailing spaces were escaped
+                    _ if options.strict => value,
+                    _ => value.trim(),
+                };
+                if value.is_empty() && c.is_eof() && ty.is_none() && avas.is_empty() {
+                    // EOF and the DN is complete
+                    break;
+                }
 
-                    rdns.push(RelativeDistinguishedName::new(rdn_type, rdn_value));
+                // If we're ending the definition of this attribute then
+                // we must already have parsed an RDN type and a value
+                let unexpected = || match c {
+                    ParseItem::Byte(c) => Error::UnexpectedCharacter(c.into()),
+                    ParseItem::Eof => Error::UnexpectedEof,
+                };
+                let ava_type = ty.take().ok_or_else(unexpected)?;
+                if value.is_empty() && (value_is_hex || !options.allow_empty_values) {
+                    return Err(unexpected());
                 }
-                // An RDN is an RDN type and a value separated by an equals
-                // sign
-                ParseItem::Byte(b'=') => {
-                    if ty.is_some() {
-                        // Something like 'a = b = c' is not a valid RDN
-                        return Err(Error::UnexpectedCharacter('='));
+
+                // Decode the value. This may be a hex encoded BER value,
+                // in which case we keep track of its original string
+                // type
+                let ava = if value_is_hex {
+                    value_is_hex = false;
+                    if let Some(max) = options
+                        .max_hex_value_length
+                        .filter(|&max| value.len() / 2 > max)
+                    {
+                        return Err(Error::HexValueTooLong(max));
                     }
+                    let (string_type, value) = der::decode_string(&hex::decode(value)?)?;
+
+                    AttributeTypeAndValue::new(ava_type, value).with_string_type(string_type)
+                } else {
+                    AttributeTypeAndValue::new(ava_type, value.to_owned())
+                };
+                acc.clear();
+                avas.push(ava);
 
-                    let ty_str = str::from_utf8(&acc)?.trim();
-                    if ty_str.is_empty() {
-                        return Err(Error::UnexpectedCharacter('='));
+                if c == ParseItem::Byte(b'+') {
+                    if !options.allow_multi_valued_rdns {
+                        return Err(Error::UnsupportedMultiValueRdns);
+                    }
+                } else {
+                    if options.max_rdns == Some(rdns.len()) {
+                        return Err(Error::TooManyRdns(rdns.len()));
                     }
+                    rdns.push(RelativeDistinguishedName {
+                        avas: mem::take(&mut avas),
+                    });
+                }
+            }
+            // An RDN is an RDN type and a value separated by an equals
+            // sign
+            ParseItem::Byte(b'=') => {
+                if ty.is_some() && options.strict {
+                    // RFC4514 does not require escaping equals signs
+                    acc.push(b'=');
+                    continue;
+                }
+                if ty.is_some() {
+                    // Something like 'a = b = c' is not a valid RDN
+                    return Err(Error::UnexpectedCharacter('='));
+                }
 
-                    ty = Some(ty_str.parse()?);
-                    acc.clear();
+                let mut ty_str = str::from_utf8(&acc)?.trim();
+                if legacy {
+                    ty_str = ty_str
+                        .strip_prefix("OID.")
+                        .or_else(|| ty_str.strip_prefix("oid."))
+                        .unwrap_or(ty_str);
+                }
+                if ty_str.is_empty() {
+                    return Err(Error::UnexpectedCharacter('='));
                 }
-                // A backslash starts an escape sequence
-                ParseItem::Byte(b'\\') => {
-                    escaping = Escaping::Started;
+
+                let parsed = ty_str.parse()?;
+                if !options.allow_unknown_types && matches!(parsed, RdnType::Other(_)) {
+                    return Err(Error::InvalidType(ty_str.to_owned()));
                 }
-                // An octothorpe right after the equals sign means that the
-                // value is an encoded hex string
-                ParseItem::Byte(b'#') => {
-                    if acc.is_empty() {
-                        value_is_hex = true;
-                    } else {
-                        acc.push(b'#');
+                ty = Some(parsed);
+                acc.clear();
+            }
+            // In the legacy format, a double quote right after the equals
+            // sign starts a quoted value
+            ParseItem::Byte(b'"')
+                if legacy
+                    && ty.is_some()
+                    && !value_is_hex
+                    && acc.iter().all(u8::is_ascii_whitespace) =>
+            {
+                acc.clear();
+                quoting = Quoting::Open;
+            }
+            // A backslash starts an escape sequence
+            ParseItem::Byte(b'\\') => {
+                escaping = Escaping::Started;
+            }
+            // An octothorpe right after the equals sign means that the
+            // value is an encoded hex string
+            ParseItem::Byte(b'#') => {
+                if acc.is_empty() {
+                    value_is_hex = true;
+                } else {
+                    acc.push(b'#');
+                }
+            }
+            // Every other byte is a literal
+            ParseItem::Byte(c) => acc.push(c),
+        }
+    }
+
+    // For some reason the string format serializes RDNs in the inverse
+    // order
+    rdns.reverse();
+
+    Ok(DistinguishedName { rdns })
+}
+
+// Check that a string conforms to the grammar of RFC4514, section 3.
+fn validate_strict(s: &str, allow_empty_values: bool) -> Result<()> {
+    let syntax = |position, message: String| Err(Error::Syntax { position, message });
+    let unexpected = |position: usize, expected: &str| {
+        let message = match s[position..].chars().next() {
+            Some(c) => format!("unexpected character {c:?}, expected {expected}"),
+            None => format!("unexpected end of string, expected {expected}"),
+        };
+        syntax(position, message)
+    };
+
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    while !s.is_empty() {
+        // The attribute type is either a name or a numeric OID
+        let start = i;
+        while bytes
+            .get(i)
+            .is_some_and(|&c| c.is_ascii_alphanumeric() || c == b'-' || c == b'.')
+        {
+            i += 1;
+        }
+        let ty = &s[start..i];
+        if ty.is_empty() {
+            return unexpected(i, "an attribute type");
+        }
+        if !is_valid_type(ty) {
+            return syntax(start, format!("invalid attribute type {ty:?}"));
+        }
+        if bytes.get(i) != Some(&b'=') {
+            return unexpected(i, "'='");
+        }
+        i += 1;
+
+        if bytes.get(i) == Some(&b'#') {
+            let start = i + 1;
+            i = start;
+            while bytes.get(i).is_some_and(u8::is_ascii_hexdigit) {
+                i += 1;
+            }
+            if i == start || !(i - start).is_multiple_of(2) {
+                return unexpected(i, "a hex digit");
+            }
+        } else {
+            let start = i;
+            let mut trailing_space = None;
+            while let Some(&c) = bytes.get(i).filter(|&&c| c != b',' && c != b'+') {
+                trailing_space = None;
+                match c {
+                    b'\\' => match bytes.get(i + 1) {
+                        Some(c) if ESCAPABLE_SYMBOLS.contains(&char::from(*c)) => i += 2,
+                        Some(c) if c.is_ascii_hexdigit() => {
+                            if !bytes.get(i + 2).is_some_and(u8::is_ascii_hexdigit) {
+                                return unexpected(i + 2, "a hex digit");
+                            }
+                            i += 3;
+                        }
+                        _ => return unexpected(i + 1, "an escapable character or hex digit"),
+                    },
+                    b'"' | b';' | b'<' | b'>' | 0 => {
+                        return syntax(i, format!("{:?} must be escaped", char::from(c)));
+                    }
+                    b' ' if i == start => {
+                        return syntax(i, "leading space must be escaped".to_owned());
+                    }
+                    _ => {
+                        if c == b' ' {
+                            trailing_space = Some(i);
+                        }
+                        i += 1;
                     }
                 }
-                // A plus sign is used to define multi-valued RDNs but we have
-                // no need for this here
-                ParseItem::Byte(b'+') => return Err(Error::UnsupportedMultiValueRdns),
-                // Every other byte is a literal
-                ParseItem::Byte(c) => acc.push(c),
             }
+            if let Some(position) = trailing_space {
+                return syntax(position, "trailing space must be escaped".to_owned());
+            }
+            if i == start && !allow_empty_values {
+                return syntax(i, "empty value".to_owned());
+            }
+        }
+
+        match bytes.get(i) {
+            None => break,
+            Some(b',' | b'+') => i += 1,
+            Some(_) => return unexpected(i, "',' or '+'"),
         }
+    }
 
-        // For some reason the string format serializes RDNs in the inverse
-        // order
-        rdns.reverse();
+    Ok(())
+}
 
-        Ok(Self { rdns })
+// Check whether an attribute type is either a `descr` or a `numericoid` as
+// defined in RFC4512.
+fn is_valid_type(ty: &str) -> bool {
+    if ty.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return !ty.contains('.');
     }
+
+    let mut numbers = ty.split('.');
+    numbers.clone().count() > 1
+        && numbers.all(|x| {
+            !x.is_empty()
+                && x.bytes().all(|c| c.is_ascii_digit())
+                && (x == "0" || !x.starts_with('0'))
+        })
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Quoting {
+    None,
+    Open,
+    Closed,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
 enum ParseItem {
     Byte(u8),
     Eof,
@@ -315,124 +980,341 @@ pub struct DnComparator {
 }
 
 impl DnComparator {
-    /// Create a new comparator from a [DistinguishedName].
+    /// Create a new comparator from a [DistinguishedName] using the standard
+    /// matching rules.
     pub fn new(dn: &DistinguishedName) -> Result<Self> {
-        let rdns = dn.iter().map(RdnComparator::new).collect::<Result<_>>()?;
+        Self::with_profile(dn, MatchingProfile::default())
+    }
+
+    /// Create a new comparator from a [DistinguishedName] using the given set
+    /// of matching rules.
+    pub fn with_profile(dn: &DistinguishedName, profile: MatchingProfile) -> Result<Self> {
+        let rdns = dn
+            .iter()
+            .map(|x| RdnComparator::with_profile(x, profile))
+            .collect::<Result<_>>()?;
 
         Ok(Self { rdns })
     }
+
+    /// Serialize into a deterministic string suitable for storage and
+    /// indexing. Two comparators are equal if and only if their canonical
+    /// strings are equal.
+    ///
+    /// The canonical string is formatted like an RFC4514 string with the
+    /// transformed values: types are written as their names as displayed by
+    /// [RdnType], or as OIDs for types without a name, every special
+    /// character of values is escaped, including every space, and the
+    /// attributes of multi-valued RDNs are sorted in byte order. This format
+    /// will not change in future versions.
+    pub fn to_canonical_string(&self) -> String {
+        let mut res = String::new();
+        for (i, rdn) in self.rdns.iter().rev().enumerate() {
+            if i > 0 {
+                res.push(',');
+            }
+
+            let mut avas = rdn.avas.iter().map(ToString::to_string).collect::<Vec<_>>();
+            avas.sort();
+            res += &avas.join("+");
+        }
+
+        res
+    }
+
+    /// Parse from the format of [Self::to_canonical_string]. Values are
+    /// taken as they are, since the matching rules used to transform them
+    /// are unknown, but the string must otherwise be exactly in canonical
+    /// form.
+    pub fn from_canonical_string(s: &str) -> Result<Self> {
+        // Values may be empty after being transformed, e.g. all spaces
+        let dn = DistinguishedName::parse_with(s, &ParserOptions::new().allow_empty_values(true))?;
+        let rdns = dn
+            .rdns
+            .into_iter()
+            .map(|rdn| {
+                let mut avas = rdn
+                    .avas
+                    .into_iter()
+                    .map(|x| AttributeComparator {
+                        ty: x.ty,
+                        value: x.value,
+                    })
+                    .collect::<Vec<_>>();
+                avas.sort();
+
+                RdnComparator { avas }
+            })
+            .collect();
+
+        // Reject alternative representations, such as type names in a
+        // different case or unescaped spaces, so that each comparator has a
+        // single canonical string
+        let res = Self { rdns };
+        if res.to_canonical_string() != s {
+            return Err(Error::NonCanonicalString(s.to_owned()));
+        }
+
+        Ok(res)
+    }
+
+    /// Convert into a comparator that ignores the order of RDNs. This is
+    /// opt-in since RFC4517 requires RDNs to be compared in order.
+    pub fn into_unordered(self) -> UnorderedDnComparator {
+        UnorderedDnComparator::from(self)
+    }
+}
+
+/// A transformed [DistinguishedName] suitable for comparisons that ignore the
+/// order of RDNs, i.e. the DN is treated as a multiset of RDNs.
+///
+/// This is useful when DNs may have been written in X.509 order, root first,
+/// instead of the RFC4514 order, but note that `CN=a,O=b` and `O=b,CN=a` are
+/// different DNs according to the standards.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UnorderedDnComparator {
+    rdns: Vec<RdnComparator>,
+}
+
+impl UnorderedDnComparator {
+    /// Create a new comparator from a [DistinguishedName] using the standard
+    /// matching rules.
+    pub fn new(dn: &DistinguishedName) -> Result<Self> {
+        DnComparator::new(dn).map(Self::from)
+    }
+
+    /// Create a new comparator from a [DistinguishedName] using the given set
+    /// of matching rules.
+    pub fn with_profile(dn: &DistinguishedName, profile: MatchingProfile) -> Result<Self> {
+        DnComparator::with_profile(dn, profile).map(Self::from)
+    }
+}
+
+impl From<DnComparator> for UnorderedDnComparator {
+    fn from(cmp: DnComparator) -> Self {
+        let mut rdns = cmp.rdns;
+        rdns.sort();
+
+        Self { rdns }
+    }
 }
 
-/// A key-value pair that is part of a [DistinguishedName].
+/// A component of a [DistinguishedName].
 ///
-/// Multi-value RDNs are not supported.
+/// Most RDNs are a single key-value pair but multi-valued RDNs, such as
+/// `CN=x+UID=y`, hold several [AttributeTypeAndValue]s.
 #[derive(Clone, Debug)]
 pub struct RelativeDistinguishedName {
+    avas: Vec<AttributeTypeAndValue>,
+}
+
+impl RelativeDistinguishedName {
+    /// Create a new single-valued RDN.
+    pub fn new(ty: RdnType, value: String) -> Self {
+        Self {
+            avas: vec![AttributeTypeAndValue::new(ty, value)],
+        }
+    }
+
+    /// Create a new multi-valued RDN. Returns `None` if no attributes are
+    /// given.
+    pub fn multi_valued(avas: Vec<AttributeTypeAndValue>) -> Option<Self> {
+        if avas.is_empty() {
+            None
+        } else {
+            Some(Self { avas })
+        }
+    }
+
+    // Check that the value of every attribute is valid for its type.
+    fn validate(&self) -> Result<()> {
+        for ava in &self.avas {
+            ava.ty.validate(&ava.value)?;
+            if let Some(x) = ava.string_type.filter(|x| !x.can_represent(&ava.value)) {
+                return Err(Error::InvalidString(x));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the type of this RDN. For multi-valued RDNs this is the type of
+    /// the first attribute.
+    pub fn ty(&self) -> RdnType {
+        self.first().ty()
+    }
+
+    /// Get the value of this RDN. For multi-valued RDNs this is the value of
+    /// the first attribute.
+    pub fn value(&self) -> &str {
+        self.first().value()
+    }
+
+    /// Get the ASN.1 string type the value of this RDN was encoded with, if
+    /// it was parsed from its BER form. For multi-valued RDNs this is the
+    /// string type of the first attribute.
+    pub fn string_type(&self) -> Option<StringType> {
+        self.first().string_type()
+    }
+
+    /// Whether this RDN holds more than one attribute.
+    pub fn is_multi_valued(&self) -> bool {
+        self.avas.len() > 1
+    }
+
+    /// Returns an iterator over all attributes of this RDN.
+    pub fn iter(&self) -> impl Iterator<Item = &AttributeTypeAndValue> {
+        self.avas.iter()
+    }
+
+    fn first(&self) -> &AttributeTypeAndValue {
+        // RDNs are never empty
+        &self.avas[0]
+    }
+}
+
+impl From<AttributeTypeAndValue> for RelativeDistinguishedName {
+    fn from(value: AttributeTypeAndValue) -> Self {
+        Self { avas: vec![value] }
+    }
+}
+
+/// A key-value pair that is part of a [RelativeDistinguishedName], usually
+/// called an AVA.
+#[derive(Clone, Debug)]
+pub struct AttributeTypeAndValue {
     ty: RdnType,
     value: String,
+    string_type: Option<StringType>,
 }
 
-impl RelativeDistinguishedName {
-    /// Create a new RDN.
+impl AttributeTypeAndValue {
+    /// Create a new AVA.
     pub fn new(ty: RdnType, value: String) -> Self {
-        Self { ty, value }
+        Self {
+            ty,
+            value,
+            string_type: None,
+        }
+    }
+
+    /// Set the ASN.1 string type used to encode the value of this AVA.
+    pub fn with_string_type(mut self, string_type: StringType) -> Self {
+        self.string_type = Some(string_type);
+        self
     }
 
-    /// Get the type of this RDN.
+    /// Get the type of this AVA.
     pub fn ty(&self) -> RdnType {
         self.ty
     }
 
-    /// Get the value of this RDN.
+    /// Get the value of this AVA.
     pub fn value(&self) -> &str {
         &self.value
     }
+
+    /// Get the ASN.1 string type the value of this AVA was encoded with, if
+    /// it was parsed from its BER form.
+    pub fn string_type(&self) -> Option<StringType> {
+        self.string_type
+    }
 }
 
 /// A transformed [RelativeDistinguishedName] suitable for comparisons.
+///
+/// The order of the attributes of multi-valued RDNs is irrelevant for
+/// comparisons as required by
+/// [RFC4517](https://datatracker.ietf.org/doc/html/rfc4517#section-4.2.15).
 #[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct RdnComparator {
-    ty: RdnType,
-    value: String,
+    avas: Vec<AttributeComparator>,
 }
 
 impl RdnComparator {
-    /// Create a new comparator from a [RelativeDistinguishedName].
+    /// Create a new comparator from a [RelativeDistinguishedName] using the
+    /// standard matching rules.
     pub fn new(rdn: &RelativeDistinguishedName) -> Result<Self> {
-        let ty = rdn.ty();
+        Self::with_profile(rdn, MatchingProfile::default())
+    }
 
-        // Prepare the value so it can be compared correctly. Comparison
-        // between values is fuzzy. Some characters must be replaced before
-        // comparison, while others must be removed.
-        //
-        // <https://datatracker.ietf.org/doc/html/rfc4518#section-2>
-        //
-        // TODO: this is not 100% complete.
-        let mut value = rdn
-            .value()
-            .chars()
-            .filter_map(|c| {
-                if c == '\u{0340}'
-                    || c == '\u{0341}'
-                    || c == '\u{200E}'
-                    || c == '\u{200F}'
-                    || ('\u{202A}'..='\u{202E}').contains(&c)
-                    || ('\u{206A}'..='\u{206F}').contains(&c)
-                    || ('\u{E000}'..='\u{F8FF}').contains(&c)
-                    || ('\u{F0000}'..='\u{FFFFD}').contains(&c)
-                    || ('\u{100000}'..='\u{10FFFD}').contains(&c)
-                    || c == '\u{FFFD}'
-                {
-                    // These characters are prohibited
-                    Some(Err(Error::UnexpectedCharacter(c)))
-                } else if c == '\u{0009}'
-                    || c == '\u{000A}'
-                    || c == '\u{000B}'
-                    || c == '\u{000C}'
-                    || c == '\u{000D}'
-                    || c == '\u{0085}'
-                    || c.is_whitespace()
-                {
-                    // These characters are compared as if they were a simple
-                    // space
-                    Some(Ok(' '))
-                } else if c == '\u{00AD}'
-                    || c == '\u{1806}'
-                    || c == '\u{034F}'
-                    || ('\u{180B}'..='\u{180D}').contains(&c)
-                    || ('\u{FE0F}'..='\u{FF00}').contains(&c)
-                    || c == '\u{FFFC}'
-                    || c.is_control()
-                    || c == '\u{200B}'
-                {
-                    // These characters are ignored during comparison
-                    None
-                } else {
-                    // Character is used in comparisons
-                    Some(Ok(c))
-                }
-            })
-            .collect::<Result<String>>()?;
-        if !ty.is_comparison_case_sensitive() {
-            value.make_ascii_lowercase();
+    /// Create a new comparator from a [RelativeDistinguishedName] using the
+    /// given set of matching rules.
+    pub fn with_profile(rdn: &RelativeDistinguishedName, profile: MatchingProfile) -> Result<Self> {
+        let mut avas = rdn
+            .iter()
+            .map(|x| AttributeComparator::with_profile(x, profile))
+            .collect::<Result<Vec<_>>>()?;
+        avas.sort();
+
+        Ok(Self { avas })
+    }
+}
+
+/// Format as an RFC4514 RDN with the transformed values, e.g. `cn=foo+uid=1`.
+impl fmt::Display for RdnComparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, ava) in self.avas.iter().enumerate() {
+            if i > 0 {
+                f.write_str("+")?;
+            }
+            write!(f, "{ava}")?;
         }
 
+        Ok(())
+    }
+}
+
+/// A transformed [AttributeTypeAndValue] suitable for comparisons.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AttributeComparator {
+    ty: RdnType,
+    value: String,
+}
+
+impl AttributeComparator {
+    /// Create a new comparator from an [AttributeTypeAndValue] using the
+    /// standard matching rules.
+    pub fn new(ava: &AttributeTypeAndValue) -> Result<Self> {
+        Self::with_profile(ava, MatchingProfile::default())
+    }
+
+    /// Create a new comparator from an [AttributeTypeAndValue] using the
+    /// given set of matching rules.
+    pub fn with_profile(ava: &AttributeTypeAndValue, profile: MatchingProfile) -> Result<Self> {
+        // Types are compared by OID, e.g. `OU` and `organizationalUnitName`
+        // are the same attribute
+        let ty = ava.ty().normalize();
+
+        // Prepare the value so it can be compared correctly. Comparison
+        // between values is fuzzy and depends on the matching rule of each
+        // type.
+        let mut value = profile.matching_rule(ty).prepare(ty, ava.value())?;
+
         // Specifically this RDN requires extra processing
         if ty == RdnType::OrganizationIdentifier {
-            value = extract_organization_id(&value)?;
+            value = extract_organization_id(&value)?.trim().to_owned();
         }
 
-        Ok(Self {
-            ty,
-            value: value.trim().to_owned(),
-        })
+        Ok(Self { ty, value })
+    }
+}
+
+/// Format as an RFC4514 attribute with the transformed value.
+impl fmt::Display for AttributeComparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut value = String::new();
+        push_escaped(&mut value, &self.value);
+
+        write!(f, "{}={value}", self.ty)
     }
 }
 
 /// A relative distinguished name type.
 ///
 /// This is the type of a single component of a full DN. We only support a
-/// select set of RDN types:
+/// select set of RDN types in string format, every other type must be given
+/// in OID format:
 ///
 /// > the Authorization Server shall accept only the AttributeTypes
 /// > (descriptors) defined in the last paragraph of clause 3 RFC4514 in
@@ -475,11 +1357,70 @@ pub enum RdnType {
     /// Participant Code associated with the CNPJ listed in the Directory
     /// Service of Open Finance Brasil.
     OrganizationalUnitName,
+    /// Any other attribute type, such as the ones added by the Certificate
+    /// Authority. These are only accepted in their OID form.
+    Other(Oid),
 }
 
 impl RdnType {
-    fn as_of_str(self) -> &'static str {
-        match self {
+    // Every type except `Other`. Note that `OU` and `organizationalUnitName`
+    // share the same OID and that `OU` takes precedence.
+    const KNOWN: [Self; 14] = [
+        Self::Cn,
+        Self::L,
+        Self::St,
+        Self::O,
+        Self::Ou,
+        Self::C,
+        Self::Street,
+        Self::Dc,
+        Self::Uid,
+        Self::BusinessCategory,
+        Self::JurisdictionCountryName,
+        Self::SerialNumber,
+        Self::OrganizationIdentifier,
+        Self::OrganizationalUnitName,
+    ];
+
+    /// Get the RDN type identified by an OID.
+    pub fn from_oid(oid: Oid) -> Self {
+        Self::KNOWN
+            .into_iter()
+            .find(|x| x.oid() == oid)
+            .unwrap_or(Self::Other(oid))
+    }
+
+    // Get the type that `from_oid` returns for the OID of this type.
+    pub(crate) fn normalize(self) -> Self {
+        Self::from_oid(self.oid())
+    }
+
+    /// Get the OID that identifies this RDN type.
+    pub fn oid(self) -> Oid {
+        let ber: &[u8] = match self {
+            Self::Cn => &[0x55, 0x04, 0x03],
+            Self::L => &[0x55, 0x04, 0x07],
+            Self::St => &[0x55, 0x04, 0x08],
+            Self::O => &[0x55, 0x04, 0x0a],
+            Self::Ou | Self::OrganizationalUnitName => &[0x55, 0x04, 0x0b],
+            Self::C => &[0x55, 0x04, 0x06],
+            Self::Street => &[0x55, 0x04, 0x09],
+            Self::Dc => &[0x09, 0x92, 0x26, 0x89, 0x93, 0xf2, 0x2c, 0x64, 0x01, 0x19],
+            Self::Uid => &[0x09, 0x92, 0x26, 0x89, 0x93, 0xf2, 0x2c, 0x64, 0x01, 0x01],
+            Self::BusinessCategory => &[0x55, 0x04, 0x0f],
+            Self::JurisdictionCountryName => &[
+                0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x3c, 0x02, 0x01, 0x03,
+            ],
+            Self::SerialNumber => &[0x55, 0x04, 0x05],
+            Self::OrganizationIdentifier => &[0x55, 0x04, 0x61],
+            Self::Other(oid) => return oid,
+        };
+
+        Oid::from_ber(ber).expect("BUG: invalid built-in OID")
+    }
+
+    fn as_of_str(self) -> Cow<'static, str> {
+        let s = match self {
             Self::Cn => "CN",
             Self::L => "L",
             Self::St => "ST",
@@ -494,7 +1435,10 @@ impl RdnType {
             Self::SerialNumber => "2.5.4.5",
             Self::OrganizationIdentifier => "2.5.4.97",
             Self::OrganizationalUnitName => "2.5.4.11",
-        }
+            Self::Other(oid) => return oid.to_string().into(),
+        };
+
+        s.into()
     }
 
     fn of_encodes_as_hex(self) -> bool {
@@ -505,21 +1449,83 @@ impl RdnType {
                 | Self::SerialNumber
                 | Self::OrganizationIdentifier
                 | Self::OrganizationalUnitName
+                | Self::Other(_)
         )
     }
 
-    fn is_comparison_case_sensitive(self) -> bool {
-        matches!(
-            self,
-            Self::Cn
-                | Self::L
-                | Self::St
-                | Self::O
-                | Self::Ou
-                | Self::C
-                | Self::JurisdictionCountryName
-                | Self::OrganizationalUnitName
-        )
+    // X.520 requires country names and serial numbers to be
+    // `PrintableString`s and RFC4519 requires domain components to be
+    // `IA5String`s. Everything else is fine as `UTF8String`.
+    pub(crate) fn default_string_type(self) -> StringType {
+        match self {
+            Self::C | Self::JurisdictionCountryName | Self::SerialNumber => {
+                StringType::PrintableString
+            }
+            Self::Dc => StringType::Ia5String,
+            _ => StringType::Utf8String,
+        }
+    }
+
+    // Check that a value is valid for this type. Values may not be empty and
+    // must be representable by the type's default string type, and country
+    // names must be two letter codes.
+    fn validate(self, value: &str) -> Result<()> {
+        let is_valid = !value.is_empty()
+            && self.default_string_type().can_represent(value)
+            && match self {
+                Self::C | Self::JurisdictionCountryName => {
+                    value.len() == 2 && value.chars().all(|c| c.is_ascii_alphabetic())
+                }
+                _ => true,
+            };
+        if !is_valid {
+            return Err(Error::InvalidValue {
+                ty: self,
+                value: value.to_owned(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Get the equality matching rule for values of this type.
+    pub fn matching_rule(self) -> MatchingRule {
+        match self {
+            // https://datatracker.ietf.org/doc/html/rfc4519#section-2.4
+            Self::Dc => MatchingRule::CaseIgnoreIa5Match,
+            // We know nothing about the matching rules of unknown types so we
+            // play it safe
+            Self::Other(_) => MatchingRule::CaseExactMatch,
+            // Every other type is defined with `caseIgnoreMatch` by either
+            // RFC4519 or X.520
+            _ => MatchingRule::CaseIgnoreMatch,
+        }
+    }
+}
+
+/// Format as the name of the type, or as an OID for types without a name.
+/// The result can be parsed back into the same type.
+impl fmt::Display for RdnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Cn => "CN",
+            Self::L => "L",
+            Self::St => "ST",
+            Self::O => "O",
+            Self::Ou => "OU",
+            Self::C => "C",
+            Self::Street => "street",
+            Self::Dc => "DC",
+            Self::Uid => "UID",
+            Self::BusinessCategory => "businessCategory",
+            Self::JurisdictionCountryName => "jurisdictionCountryName",
+            Self::SerialNumber => "serialNumber",
+            Self::OrganizationIdentifier => "organizationIdentifier",
+            Self::OrganizationalUnitName => "organizationalUnitName",
+            Self::Other(oid) => return write!(f, "{oid}"),
+        };
+
+        f.write_str(s)
     }
 }
 
@@ -560,11 +1566,26 @@ impl FromStr for RdnType {
             "organizationidentifier" | "2.5.4.97" => Ok(Self::OrganizationIdentifier),
             // https://openfinancebrasil.atlassian.net/wiki/spaces/OF/pages/240650099/EN+Padr+o+de+Certificados+Open+Finance+Brasil+2.0#5.2.2.1.-Open-Finance-Brasil-Attributes
             "organizationalunitname" | "2.5.4.11" => Ok(Self::OrganizationalUnitName),
+            // Any other attribute type must be in its OID form
+            _ if s.starts_with(|c: char| c.is_ascii_digit()) => Ok(Self::Other(s.parse()?)),
             _ => Err(Error::InvalidType(s.to_owned())),
         }
     }
 }
 
+// Append a value to a string escaping special characters.
+fn push_escaped(res: &mut String, value: &str) {
+    res.reserve(value.len());
+    for c in value.chars() {
+        if ESCAPABLE_SYMBOLS.contains(&c) {
+            // Note: for simplicity we'll be escaping everything we can
+            // unconditionally even when this is not necesary
+            res.push('\\');
+        }
+        res.push(c);
+    }
+}
+
 // Clean the value of `organizationIdentifier` according to the OF spec.
 //
 // One day the people working on the OpenFinance spec woke up with the most
@@ -0,0 +1,78 @@
+//! Equality matching rules used to compare attribute values:
+//! <https://datatracker.ietf.org/doc/html/rfc4517#section-4.2>.
+
+use crate::{prep, Error, RdnType, Result};
+
+/// An equality matching rule.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchingRule {
+    /// `caseIgnoreMatch`: case and insignificant spaces are ignored.
+    CaseIgnoreMatch,
+    /// `caseExactMatch`: insignificant spaces are ignored.
+    CaseExactMatch,
+    /// `caseIgnoreIA5Match`: like `caseIgnoreMatch` but only defined for
+    /// ASCII values.
+    CaseIgnoreIa5Match,
+}
+
+impl MatchingRule {
+    /// Transform a value so that two values match under this rule if and
+    /// only if their transformed forms are equal.
+    pub(crate) fn prepare(self, ty: RdnType, value: &str) -> Result<String> {
+        match self {
+            Self::CaseIgnoreMatch => prep::prepare(value, true),
+            Self::CaseExactMatch => prep::prepare(value, false),
+            Self::CaseIgnoreIa5Match => {
+                if !value.is_ascii() {
+                    return Err(Error::InvalidValue {
+                        ty,
+                        value: value.to_owned(),
+                    });
+                }
+
+                prep::prepare(value, true)
+            }
+        }
+    }
+}
+
+/// The set of matching rules used when comparing DNs.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub enum MatchingProfile {
+    /// Use the equality matching rule of each RDN type, as defined by
+    /// RFC4519 and X.520.
+    #[default]
+    Standard,
+    /// Use the case sensitivity of older versions of this crate: `CN`, `L`,
+    /// `ST`, `O`, `OU`, `C`, `jurisdictionCountryName`,
+    /// `organizationalUnitName` and unknown types are compared with
+    /// `caseExactMatch` and everything else with `caseIgnoreMatch`.
+    ///
+    /// Values are still prepared as described in RFC4518, with Unicode
+    /// normalization, full case folding and collapsing of inner spaces,
+    /// while older versions only trimmed values and lowercased ASCII
+    /// letters. Values with non-ASCII characters or repeated spaces may thus
+    /// compare differently than they used to.
+    OpenFinanceCompat,
+}
+
+impl MatchingProfile {
+    /// Get the matching rule used for values of the given RDN type.
+    pub fn matching_rule(self, ty: RdnType) -> MatchingRule {
+        match self {
+            Self::Standard => ty.matching_rule(),
+            Self::OpenFinanceCompat => match ty {
+                RdnType::Cn
+                | RdnType::L
+                | RdnType::St
+                | RdnType::O
+                | RdnType::Ou
+                | RdnType::C
+                | RdnType::JurisdictionCountryName
+                | RdnType::OrganizationalUnitName
+                | RdnType::Other(_) => MatchingRule::CaseExactMatch,
+                _ => MatchingRule::CaseIgnoreMatch,
+            },
+        }
+    }
+}
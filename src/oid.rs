@@ -0,0 +1,141 @@
+//! ASN.1 object identifiers.
+
+use std::{fmt, str::FromStr};
+
+use crate::{Error, Result};
+
+// Maximum length of the BER encoding of the OIDs we support. This is plenty
+// for any attribute type out there and lets us keep `Oid` inline and `Copy`.
+const MAX_LEN: usize = 39;
+
+/// An ASN.1 object identifier (OID) such as `2.5.4.3`.
+///
+/// OIDs are kept in their BER encoded form. Only OIDs whose encoding fits in
+/// 39 bytes are supported.
+#[derive(Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Oid {
+    len: u8,
+    bytes: [u8; MAX_LEN],
+}
+
+impl Oid {
+    /// Create an OID from the contents octets of its BER encoding.
+    pub fn from_ber(ber: &[u8]) -> Result<Self> {
+        let invalid = || Error::InvalidOid(hex::encode(ber));
+
+        // Every arc must be minimally encoded, fit in a `u128` and the last
+        // byte must end an arc
+        let is_valid = ber.len() <= MAX_LEN
+            && ber.last().is_some_and(|b| b & 0x80 == 0)
+            && ber
+                .iter()
+                .enumerate()
+                .all(|(i, &b)| b != 0x80 || (i > 0 && ber[i - 1] & 0x80 != 0))
+            && ber.split_inclusive(|b| b & 0x80 == 0).all(|x| {
+                let bits = 7 * (x.len() - 1) as u32 + (8 - (x[0] & 0x7f).leading_zeros());
+                bits <= u128::BITS
+            });
+        if !is_valid {
+            return Err(invalid());
+        }
+
+        let mut bytes = [0; MAX_LEN];
+        bytes[..ber.len()].copy_from_slice(ber);
+
+        Ok(Self {
+            len: ber.len() as u8,
+            bytes,
+        })
+    }
+
+    /// Get the contents octets of the BER encoding of this OID.
+    pub fn as_ber(&self) -> &[u8] {
+        &self.bytes[..usize::from(self.len)]
+    }
+
+    /// Returns an iterator over the arcs of this OID.
+    pub fn arcs(&self) -> impl Iterator<Item = u128> + '_ {
+        let mut subidentifiers = self.as_ber().split_inclusive(|b| b & 0x80 == 0).map(|x| {
+            x.iter()
+                .fold(0u128, |acc, &b| (acc << 7) | u128::from(b & 0x7f))
+        });
+
+        // The first subidentifier packs the first two arcs
+        let first = subidentifiers.next().unwrap_or_default();
+        let (a, b) = match first {
+            0..=39 => (0, first),
+            40..=79 => (1, first - 40),
+            _ => (2, first - 80),
+        };
+
+        [a, b].into_iter().chain(subidentifiers)
+    }
+}
+
+impl fmt::Display for Oid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, arc) in self.arcs().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            write!(f, "{arc}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Oid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Oid({self})")
+    }
+}
+
+/// Parse from the dotted decimal format.
+impl FromStr for Oid {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidOid(s.to_owned());
+
+        let arcs = s
+            .split('.')
+            .map(|arc| {
+                // Leading zeros are not allowed
+                let is_valid = !arc.is_empty()
+                    && arc.bytes().all(|b| b.is_ascii_digit())
+                    && (arc == "0" || !arc.starts_with('0'));
+                if is_valid {
+                    arc.parse::<u128>().map_err(|_| invalid())
+                } else {
+                    Err(invalid())
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let [first, second, rest @ ..] = arcs.as_slice() else {
+            return Err(invalid());
+        };
+        if *first > 2 || (*first < 2 && *second > 39) {
+            return Err(invalid());
+        }
+        let first = second.checked_add(first * 40).ok_or_else(invalid)?;
+
+        let mut ber = Vec::new();
+        for &arc in [first].iter().chain(rest) {
+            let start = ber.len();
+            let mut arc = arc;
+            loop {
+                ber.push((arc & 0x7f) as u8 | 0x80);
+                arc >>= 7;
+                if arc == 0 {
+                    break;
+                }
+            }
+            ber[start..].reverse();
+            *ber.last_mut().unwrap() &= 0x7f;
+        }
+
+        Self::from_ber(&ber).map_err(|_| invalid())
+    }
+}
@@ -0,0 +1,122 @@
+//! Typed access to the attributes of OpenFinance Brasil client certificates.
+
+use crate::{Cnpj, DistinguishedName, Error, RdnType, Result};
+
+/// The subject of an OpenFinance Brasil client certificate.
+///
+/// Every attribute required by the certificate standard must be present
+/// exactly once, except for `ST` and `L` which are optional:
+/// <https://openfinancebrasil.atlassian.net/wiki/spaces/OF/pages/240650099/EN+Padr+o+de+Certificados+Open+Finance+Brasil+2.0#5.2.2.1.-Open-Finance-Brasil-Attributes>
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OpenFinanceSubject {
+    common_name: String,
+    software_statement_id: String,
+    organization_id: String,
+    cnpj: Cnpj,
+    business_category: String,
+    jurisdiction_country_name: String,
+    country_name: String,
+    organization_name: String,
+    state_or_province_name: Option<String>,
+    locality_name: Option<String>,
+}
+
+impl OpenFinanceSubject {
+    /// Get the common name (`CN`), usually the FQDN of the application.
+    pub fn common_name(&self) -> &str {
+        &self.common_name
+    }
+
+    /// Get the software statement ID registered in the Directory (`UID`).
+    pub fn software_statement_id(&self) -> &str {
+        &self.software_statement_id
+    }
+
+    /// Get the organization ID registered in the Directory. See
+    /// [DistinguishedName::organization_id].
+    pub fn organization_id(&self) -> &str {
+        &self.organization_id
+    }
+
+    /// Get the CNPJ of the legal entity holding the certificate
+    /// (`serialNumber`).
+    pub fn cnpj(&self) -> Cnpj {
+        self.cnpj
+    }
+
+    /// Get the type of business category (`businessCategory`).
+    pub fn business_category(&self) -> &str {
+        &self.business_category
+    }
+
+    /// Get the jurisdiction country name (`jurisdictionCountryName`).
+    pub fn jurisdiction_country_name(&self) -> &str {
+        &self.jurisdiction_country_name
+    }
+
+    /// Get the country name (`C`).
+    pub fn country_name(&self) -> &str {
+        &self.country_name
+    }
+
+    /// Get the organization name (`O`).
+    pub fn organization_name(&self) -> &str {
+        &self.organization_name
+    }
+
+    /// Get the state or province name (`ST`), if present.
+    pub fn state_or_province_name(&self) -> Option<&str> {
+        self.state_or_province_name.as_deref()
+    }
+
+    /// Get the locality name (`L`), if present.
+    pub fn locality_name(&self) -> Option<&str> {
+        self.locality_name.as_deref()
+    }
+}
+
+impl TryFrom<&DistinguishedName> for OpenFinanceSubject {
+    type Error = Error;
+
+    fn try_from(dn: &DistinguishedName) -> Result<Self> {
+        let required = |ty| find_unique(dn, ty)?.ok_or(Error::MissingAttribute(ty));
+
+        // The organization ID may be in one of several attributes but none
+        // of them may be repeated
+        for ty in [RdnType::Ou, RdnType::OrganizationIdentifier] {
+            find_unique(dn, ty)?;
+        }
+        let organization_id = dn
+            .organization_id()?
+            .ok_or(Error::MissingAttribute(RdnType::Ou))?;
+
+        Ok(Self {
+            common_name: required(RdnType::Cn)?,
+            software_statement_id: required(RdnType::Uid)?,
+            organization_id: organization_id.into_owned(),
+            cnpj: required(RdnType::SerialNumber)?.parse()?,
+            business_category: required(RdnType::BusinessCategory)?,
+            jurisdiction_country_name: required(RdnType::JurisdictionCountryName)?,
+            country_name: required(RdnType::C)?,
+            organization_name: required(RdnType::O)?,
+            state_or_province_name: find_unique(dn, RdnType::St)?,
+            locality_name: find_unique(dn, RdnType::L)?,
+        })
+    }
+}
+
+// Find the value of an attribute that must not be repeated. Types are
+// compared by OID, e.g. `OU` and `organizationalUnitName` are the same
+// attribute.
+fn find_unique(dn: &DistinguishedName, ty: RdnType) -> Result<Option<String>> {
+    let mut values = dn
+        .iter()
+        .flat_map(|x| x.iter())
+        .filter(|x| x.ty().oid() == ty.oid());
+    let value = values.next().map(|x| x.value().to_owned());
+    if values.next().is_some() {
+        return Err(Error::DuplicateAttribute(ty));
+    }
+
+    Ok(value)
+}
@@ -0,0 +1,531 @@
+//! Compatibility with the way OpenSSL handles DNs.
+
+use std::{fmt::Write, mem};
+
+use sha1::{Digest, Sha1};
+
+use crate::{
+    der, AttributeTypeAndValue, DerOptions, DistinguishedName, Error, RdnType,
+    RelativeDistinguishedName, Result, StringType,
+};
+
+// Short and long names OpenSSL uses for common attribute types
+const NAMES: [(&str, &str, &str); 26] = [
+    ("CN", "commonName", "2.5.4.3"),
+    ("SN", "surname", "2.5.4.4"),
+    ("serialNumber", "serialNumber", "2.5.4.5"),
+    ("C", "countryName", "2.5.4.6"),
+    ("L", "localityName", "2.5.4.7"),
+    ("ST", "stateOrProvinceName", "2.5.4.8"),
+    ("street", "streetAddress", "2.5.4.9"),
+    ("O", "organizationName", "2.5.4.10"),
+    ("OU", "organizationalUnitName", "2.5.4.11"),
+    ("title", "title", "2.5.4.12"),
+    ("description", "description", "2.5.4.13"),
+    ("businessCategory", "businessCategory", "2.5.4.15"),
+    ("postalCode", "postalCode", "2.5.4.17"),
+    ("name", "name", "2.5.4.41"),
+    ("GN", "givenName", "2.5.4.42"),
+    ("initials", "initials", "2.5.4.43"),
+    ("generationQualifier", "generationQualifier", "2.5.4.44"),
+    ("dnQualifier", "dnQualifier", "2.5.4.46"),
+    ("pseudonym", "pseudonym", "2.5.4.65"),
+    (
+        "organizationIdentifier",
+        "organizationIdentifier",
+        "2.5.4.97",
+    ),
+    ("UID", "userId", "0.9.2342.19200300.100.1.1"),
+    ("DC", "domainComponent", "0.9.2342.19200300.100.1.25"),
+    ("emailAddress", "emailAddress", "1.2.840.113549.1.9.1"),
+    (
+        "jurisdictionL",
+        "jurisdictionLocalityName",
+        "1.3.6.1.4.1.311.60.2.1.1",
+    ),
+    (
+        "jurisdictionST",
+        "jurisdictionStateOrProvinceName",
+        "1.3.6.1.4.1.311.60.2.1.2",
+    ),
+    (
+        "jurisdictionC",
+        "jurisdictionCountryName",
+        "1.3.6.1.4.1.311.60.2.1.3",
+    ),
+];
+
+/// A DN string format used by OpenSSL, such as in the output of `openssl x509
+/// -subject`.
+///
+/// Except for [OpensslFormat::Rfc2253], these formats list RDNs in the order
+/// they appear in certificates, starting from the root.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum OpensslFormat {
+    /// The format of `-nameopt RFC2253`, e.g. `CN=bar,O=Foo,C=BR`.
+    Rfc2253,
+    /// The legacy format of `X509_NAME_oneline` and `-nameopt compat`, e.g.
+    /// `/C=BR/O=Foo/CN=bar`.
+    Slash,
+    /// The format of `-nameopt oneline`, e.g. `C = BR, O = Foo, CN = bar`.
+    Oneline,
+    /// The format of `-nameopt multiline`, with one RDN per line and long
+    /// type names, e.g. `countryName = BR`.
+    Multiline,
+}
+
+impl DistinguishedName {
+    /// Parse from one of the formats used by OpenSSL. A leading `subject=`
+    /// or `issuer=`, as printed by `openssl x509`, is ignored.
+    ///
+    /// The resulting RDNs are in the same order as when parsing the
+    /// equivalent RFC4514 string with [std::str::FromStr].
+    pub fn from_openssl_str(s: &str, format: OpensslFormat) -> Result<Self> {
+        let s = s.trim();
+        let s = s
+            .strip_prefix("subject=")
+            .or_else(|| s.strip_prefix("issuer="))
+            .unwrap_or(s)
+            .trim();
+
+        let rdns = match format {
+            OpensslFormat::Rfc2253 => {
+                let mut rdns = parse_oneline(s, true)?;
+                rdns.reverse();
+                rdns.iter_mut().for_each(|x| x.avas.reverse());
+                rdns
+            }
+            OpensslFormat::Slash => parse_slash(s)?,
+            OpensslFormat::Oneline => parse_oneline(s, false)?,
+            OpensslFormat::Multiline => parse_multiline(s)?,
+        };
+
+        Ok(Self { rdns })
+    }
+
+    /// Serialize into one of the formats used by OpenSSL, with the same
+    /// attribute names and escaping, but without the `subject=` prefix.
+    ///
+    /// Non-ASCII characters are escaped unless `utf8` is set, which
+    /// corresponds to `-nameopt utf8,-esc_msb` or, for the slash format, to
+    /// passing the result to `-subj` along with `-utf8`.
+    pub fn to_openssl_string(&self, format: OpensslFormat, utf8: bool) -> String {
+        let (rdn_separator, ava_separator) = match format {
+            OpensslFormat::Rfc2253 => (",", "+"),
+            OpensslFormat::Slash => ("/", "+"),
+            OpensslFormat::Oneline => (", ", " + "),
+            OpensslFormat::Multiline => ("\n", " + "),
+        };
+
+        // RFC2253 lists everything in reverse, including the attributes of
+        // multi-valued RDNs
+        let mut rdns = self
+            .rdns
+            .iter()
+            .map(|rdn| rdn.iter().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        if format == OpensslFormat::Rfc2253 {
+            rdns.reverse();
+            rdns.iter_mut().for_each(|x| x.reverse());
+        }
+
+        let mut res = String::new();
+        for (i, avas) in rdns.into_iter().enumerate() {
+            if i > 0 || format == OpensslFormat::Slash {
+                res += rdn_separator;
+            }
+            if format == OpensslFormat::Multiline {
+                res += "    ";
+            }
+
+            for (j, ava) in avas.into_iter().enumerate() {
+                if j > 0 {
+                    res += ava_separator;
+                }
+
+                // Only named types are aligned and types without a name are
+                // dumped as DER in RFC2253
+                let name = type_name(ava.ty(), format == OpensslFormat::Multiline);
+                let _ = match (format, name) {
+                    (OpensslFormat::Multiline, Some(name)) => write!(res, "{name:<25} = "),
+                    (OpensslFormat::Multiline | OpensslFormat::Oneline, None) => {
+                        write!(res, "{} = ", ava.ty().oid())
+                    }
+                    (OpensslFormat::Oneline, Some(name)) => write!(res, "{name} = "),
+                    (_, Some(name)) => write!(res, "{name}="),
+                    (_, None) => write!(res, "{}=", ava.ty().oid()),
+                };
+                if format == OpensslFormat::Rfc2253 && name.is_none() {
+                    let string_type = DerOptions::default().string_type_for(ava);
+                    res.push('#');
+                    res += &hex::encode_upper(der::encode_string(string_type, ava.value()));
+                } else {
+                    push_escaped(&mut res, ava.value(), format, utf8);
+                }
+            }
+        }
+
+        res
+    }
+
+    /// Compute the canonical encoding OpenSSL uses to compare and hash DNs.
+    ///
+    /// This is the DER encoding of every RDN, concatenated without the outer
+    /// `SEQUENCE`, with every string value re-encoded as a `UTF8String`
+    /// after removing leading and trailing whitespace, collapsing inner runs
+    /// of whitespace into a single space and lowercasing ASCII letters.
+    /// `NumericString` values are left untouched as OpenSSL does.
+    pub fn openssl_canonical_encoding(&self) -> Vec<u8> {
+        let options = DerOptions::default();
+
+        let mut res = Vec::new();
+        for rdn in &self.rdns {
+            der::encode_rdn(&mut res, rdn, |ava| {
+                let string_type = options.string_type_for(ava);
+                if string_type == StringType::NumericString {
+                    return der::encode_string(string_type, ava.value());
+                }
+
+                der::encode_string(StringType::Utf8String, &canonicalize(ava.value()))
+            });
+        }
+
+        res
+    }
+
+    /// Compute the same hash as OpenSSL's `X509_NAME_hash`, which is used to
+    /// name files in hashed certificate directories, e.g. `{hash:08x}.0`.
+    /// Also known as the subject hash, or the issuer hash when used to find
+    /// the certificate of an issuer.
+    pub fn openssl_hash(&self) -> u32 {
+        let digest = Sha1::digest(self.openssl_canonical_encoding());
+
+        u32::from_le_bytes([digest[0], digest[1], digest[2], digest[3]])
+    }
+}
+
+// Transform a value like OpenSSL's `asn1_string_canon`. Only ASCII
+// characters are affected.
+fn canonicalize(value: &str) -> String {
+    let mut res = String::with_capacity(value.len());
+    for word in value.split(is_space).filter(|x| !x.is_empty()) {
+        if !res.is_empty() {
+            res.push(' ');
+        }
+        res.extend(word.chars().map(|c| c.to_ascii_lowercase()));
+    }
+
+    res
+}
+
+// Whitespace according to C's `isspace` in the C locale.
+fn is_space(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\u{0b}' | '\u{0c}' | '\r')
+}
+
+// Get the short or long name OpenSSL uses for an attribute type, if it has
+// one.
+fn type_name(ty: RdnType, long: bool) -> Option<&'static str> {
+    let oid = ty.oid().to_string();
+    let (sn, ln, _) = NAMES.iter().find(|x| x.2 == oid)?;
+
+    Some(if long { ln } else { sn })
+}
+
+// Escape a value the way OpenSSL does in the given format. Control
+// characters, and non-ASCII ones unless `utf8` is set, are escaped as UTF-8
+// bytes or, in the multiline format, as code points.
+fn push_escaped(res: &mut String, value: &str, format: OpensslFormat, utf8: bool) {
+    // The oneline format quotes values instead of escaping separators
+    let quoted = format == OpensslFormat::Oneline
+        && (value.starts_with(['#', ' '])
+            || value.ends_with(' ')
+            || value.contains([',', '+', ';', '<', '>']));
+    if quoted {
+        res.push('"');
+    }
+
+    for (i, c) in value.char_indices() {
+        let is_special = match format {
+            OpensslFormat::Rfc2253 => {
+                matches!(c, ',' | '+' | '"' | '\\' | '<' | '>' | ';')
+                    || (i == 0 && matches!(c, '#' | ' '))
+                    || (c == ' ' && i + 1 == value.len())
+            }
+            OpensslFormat::Oneline => matches!(c, '"' | '\\'),
+            OpensslFormat::Slash => matches!(c, '/' | '+'),
+            OpensslFormat::Multiline => c == '\\',
+        };
+        if is_special {
+            res.push('\\');
+            res.push(c);
+            continue;
+        }
+        if !c.is_ascii_control() && (c.is_ascii() || utf8) {
+            res.push(c);
+            continue;
+        }
+
+        let _ = match (format, u32::from(c)) {
+            (OpensslFormat::Multiline, x @ ..=0xff) => write!(res, "\\{x:02X}"),
+            (OpensslFormat::Multiline, x @ ..=0xffff) => write!(res, "\\U{x:04X}"),
+            (OpensslFormat::Multiline, x) => write!(res, "\\W{x:08X}"),
+            (OpensslFormat::Slash, _) => c
+                .encode_utf8(&mut [0; 4])
+                .bytes()
+                .try_for_each(|x| write!(res, "\\x{x:02X}")),
+            _ => c
+                .encode_utf8(&mut [0; 4])
+                .bytes()
+                .try_for_each(|x| write!(res, "\\{x:02X}")),
+        };
+    }
+
+    if quoted {
+        res.push('"');
+    }
+}
+
+// Parse `/type=value/type=value+type=value`. Slashes and plus signs are not
+// escaped by OpenSSL so they are only treated as separators when followed by
+// an attribute type and an equals sign.
+fn parse_slash(s: &str) -> Result<Vec<RelativeDistinguishedName>> {
+    let mut rdns = Vec::new();
+    let mut avas = Vec::new();
+    let Some(mut rest) = s.strip_prefix('/') else {
+        return match s.chars().next() {
+            Some(c) => Err(Error::UnexpectedCharacter(c)),
+            None => Ok(rdns),
+        };
+    };
+
+    loop {
+        let mut escaping = false;
+        let end = rest.char_indices().find(|&(i, c)| {
+            let is_separator =
+                !escaping && matches!(c, '/' | '+') && starts_with_type(&rest[i + 1..]);
+            escaping = !escaping && c == '\\';
+            is_separator
+        });
+        let (ava, separator) = match end {
+            Some((i, c)) => (&rest[..i], Some(c)),
+            None => (rest, None),
+        };
+
+        let (ty, value) = ava.split_once('=').ok_or(Error::UnexpectedEof)?;
+        avas.push(new_ava(ty, value, Escapes::Slash)?);
+        if separator != Some('+') {
+            rdns.push(RelativeDistinguishedName {
+                avas: mem::take(&mut avas),
+            });
+        }
+
+        match separator {
+            Some(_) => rest = &rest[ava.len() + 1..],
+            None => return Ok(rdns),
+        }
+    }
+}
+
+// Parse `type = value, type = value + type = value`, where values may be
+// quoted or escaped as in RFC2253 with non-ASCII characters escaped as UTF-8
+// bytes. With `hex_values`, values starting with an unescaped `#` are hex
+// encoded DER values.
+fn parse_oneline(s: &str, hex_values: bool) -> Result<Vec<RelativeDistinguishedName>> {
+    let mut rdns = Vec::new();
+    let mut avas = Vec::new();
+    if s.is_empty() {
+        return Ok(rdns);
+    }
+
+    let mut chars = s.chars().peekable();
+    loop {
+        let mut ty = String::new();
+        loop {
+            match chars.next().ok_or(Error::UnexpectedEof)? {
+                '=' => break,
+                c => ty.push(c),
+            }
+        }
+
+        let mut value = String::new();
+        while chars.next_if_eq(&' ').is_some() {}
+        let is_hex = hex_values && chars.next_if_eq(&'#').is_some();
+
+        if !is_hex && chars.next_if_eq(&'"').is_some() {
+            loop {
+                match chars.next().ok_or(Error::UnexpectedEof)? {
+                    '"' => break,
+                    '\\' => {
+                        value.push('\\');
+                        value.push(chars.next().ok_or(Error::UnexpectedEof)?);
+                    }
+                    c => value.push(c),
+                }
+            }
+            while chars.next_if_eq(&' ').is_some() {}
+        } else {
+            // Trailing spaces are not part of the value unless escaped
+            let mut len = 0;
+            while let Some(c) = chars.next_if(|&c| c != ',' && c != '+') {
+                value.push(c);
+                if c == '\\' {
+                    value.push(chars.next().ok_or(Error::UnexpectedEof)?);
+                }
+                if c != ' ' {
+                    len = value.len();
+                }
+            }
+            value.truncate(len);
+        }
+
+        if is_hex {
+            let (string_type, value) = der::decode_string(&hex::decode(&value)?)?;
+            let ava = AttributeTypeAndValue::new(parse_type(ty.trim())?, value);
+            avas.push(ava.with_string_type(string_type));
+        } else {
+            avas.push(new_ava(&ty, &value, Escapes::Bytes)?);
+        }
+
+        match chars.next() {
+            Some('+') => continue,
+            Some(',') => rdns.push(RelativeDistinguishedName {
+                avas: mem::take(&mut avas),
+            }),
+            None => {
+                rdns.push(RelativeDistinguishedName { avas });
+                return Ok(rdns);
+            }
+            Some(c) => return Err(Error::UnexpectedCharacter(c)),
+        }
+    }
+}
+
+// Parse one RDN per line, each being `type = value + type = value`. Values
+// are not escaped except for control and non-ASCII characters, which are
+// escaped as code points.
+fn parse_multiline(s: &str) -> Result<Vec<RelativeDistinguishedName>> {
+    let mut rdns = Vec::new();
+    for line in s.lines().map(str::trim).filter(|x| !x.is_empty()) {
+        let mut avas = Vec::new();
+        let mut rest = line;
+        loop {
+            let end = rest
+                .match_indices(" + ")
+                .map(|(i, _)| i)
+                .find(|&i| starts_with_type(&rest[i + 3..]));
+            let ava = &rest[..end.unwrap_or(rest.len())];
+
+            let (ty, value) = ava.split_once('=').ok_or(Error::UnexpectedEof)?;
+            avas.push(new_ava(ty, value.trim(), Escapes::CodePoints)?);
+
+            match end {
+                Some(i) => rest = &rest[i + 3..],
+                None => break,
+            }
+        }
+
+        rdns.push(RelativeDistinguishedName { avas });
+    }
+
+    Ok(rdns)
+}
+
+// Check whether a string starts with an attribute type followed by an equals
+// sign, possibly with spaces in between.
+fn starts_with_type(s: &str) -> bool {
+    let Some((ty, _)) = s.split_once('=') else {
+        return false;
+    };
+    let ty = ty.trim_end();
+
+    !ty.is_empty()
+        && ty
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+}
+
+fn new_ava(ty: &str, value: &str, escapes: Escapes) -> Result<AttributeTypeAndValue> {
+    let ty = parse_type(ty.trim())?;
+    let value = unescape(value, escapes).ok_or_else(|| Error::InvalidValue {
+        ty,
+        value: value.to_owned(),
+    })?;
+    if value.is_empty() {
+        return Err(Error::InvalidValue { ty, value });
+    }
+
+    Ok(AttributeTypeAndValue::new(ty, value))
+}
+
+// Get an attribute type from its OpenSSL short or long name, falling back to
+// the names we know and OIDs.
+fn parse_type(name: &str) -> Result<RdnType> {
+    match NAMES.iter().find(|(sn, ln, _)| name == *sn || name == *ln) {
+        Some((_, _, oid)) => Ok(RdnType::from_oid(
+            oid.parse().expect("BUG: invalid built-in OID"),
+        )),
+        None => name.parse(),
+    }
+}
+
+// How OpenSSL escapes characters in each format.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Escapes {
+    // `\xHH` is a byte
+    Slash,
+    // `\HH` is a byte
+    Bytes,
+    // `\HH`, `\UHHHH` and `\WHHHHHHHH` are code points
+    CodePoints,
+}
+
+// Decode escape sequences. A backslash followed by anything other than an
+// escape sequence stands for the character following it, except in the slash
+// format where it only escapes separators and itself. Returns `None` for
+// malformed escapes and invalid UTF-8.
+fn unescape(value: &str, escapes: Escapes) -> Option<String> {
+    let mut res = Vec::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        let c = match c {
+            '\\' => chars.next()?,
+            _ => {
+                res.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                continue;
+            }
+        };
+
+        // The first digit of `\HH` is the escaped character itself
+        let (mut code, digits) = match (escapes, c) {
+            (Escapes::Slash, 'x') => (0, 2),
+            (Escapes::Bytes | Escapes::CodePoints, _) if c.is_ascii_hexdigit() => {
+                (c.to_digit(16)?, 1)
+            }
+            (Escapes::CodePoints, 'U') => (0, 4),
+            (Escapes::CodePoints, 'W') => (0, 8),
+            // OpenSSL does not escape backslashes in the slash format
+            (Escapes::Slash, _) if !matches!(c, '/' | '+' | '\\') => {
+                res.push(b'\\');
+                res.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                continue;
+            }
+            _ => {
+                res.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                continue;
+            }
+        };
+
+        for _ in 0..digits {
+            code = code << 4 | chars.next()?.to_digit(16)?;
+        }
+
+        if escapes == Escapes::CodePoints {
+            res.extend_from_slice(char::from_u32(code)?.encode_utf8(&mut [0; 4]).as_bytes());
+        } else {
+            res.push(code as u8);
+        }
+    }
+
+    String::from_utf8(res).ok()
+}
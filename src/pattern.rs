@@ -0,0 +1,139 @@
+//! Matching of DNs against patterns with wildcards, e.g. for allowlists.
+
+use std::str::FromStr;
+
+use crate::{
+    AttributeComparator, AttributeTypeAndValue, DistinguishedName, Error, MatchingProfile, RdnType,
+    Result,
+};
+
+/// A DN pattern such as `CN=*,OU={org_id},O=ICP-Brasil,C=BR`.
+///
+/// Patterns use the same syntax as DN strings, except that a value of `*`
+/// matches any value and a value of `{name}` matches any value and captures
+/// it under the given name. Every other value must match exactly, using the
+/// same rules as [crate::DnComparator]. Note that this means that values
+/// consisting solely of `*` or of a name between braces can't be matched
+/// exactly.
+///
+/// A DN matches a pattern if it has the same number of RDNs and each of them
+/// has the same attribute types as the corresponding RDN of the pattern.
+#[derive(Clone, Debug)]
+pub struct DnPattern {
+    rdns: Vec<Vec<(RdnType, ValuePattern)>>,
+}
+
+#[derive(Clone, Debug)]
+enum ValuePattern {
+    Any,
+    Capture(String),
+    Exact(AttributeTypeAndValue),
+}
+
+impl DnPattern {
+    /// Match a DN against this pattern using the standard matching rules.
+    /// Returns the captured values if the DN matches.
+    pub fn matches(&self, dn: &DistinguishedName) -> Result<Option<DnCaptures>> {
+        self.matches_with(dn, MatchingProfile::default())
+    }
+
+    /// Match a DN against this pattern using the given set of matching rules.
+    /// Returns the captured values if the DN matches.
+    pub fn matches_with(
+        &self,
+        dn: &DistinguishedName,
+        profile: MatchingProfile,
+    ) -> Result<Option<DnCaptures>> {
+        if dn.rdns.len() != self.rdns.len() {
+            return Ok(None);
+        }
+
+        let mut captures = Vec::new();
+        for (pattern, rdn) in self.rdns.iter().rev().zip(dn.rdns.iter().rev()) {
+            // Pair attributes by type, the order of the attributes of
+            // multi-valued RDNs being irrelevant
+            let mut avas = rdn.iter().collect::<Vec<_>>();
+            avas.sort_by_key(|x| x.ty().normalize());
+            if !pattern
+                .iter()
+                .map(|x| x.0)
+                .eq(avas.iter().map(|x| x.ty().normalize()))
+            {
+                return Ok(None);
+            }
+
+            for ((_, value), ava) in pattern.iter().zip(avas) {
+                match value {
+                    ValuePattern::Any => {}
+                    ValuePattern::Capture(name) => {
+                        captures.push((name.clone(), ava.value().to_owned()));
+                    }
+                    ValuePattern::Exact(expected) => {
+                        let expected = AttributeComparator::with_profile(expected, profile)?;
+                        if AttributeComparator::with_profile(ava, profile)? != expected {
+                            return Ok(None);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(Some(DnCaptures { captures }))
+    }
+}
+
+/// Parse from the DN pattern string format.
+impl FromStr for DnPattern {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let dn = DistinguishedName::from_str(s)?;
+
+        let mut names = Vec::new();
+        let mut rdns = Vec::with_capacity(dn.rdns.len());
+        for rdn in dn.rdns {
+            let mut avas = Vec::with_capacity(rdn.avas.len());
+            for ava in rdn.avas {
+                let value = match ava.value() {
+                    "*" => ValuePattern::Any,
+                    x if x.starts_with('{') && x.ends_with('}') => {
+                        let name = &x[1..x.len() - 1];
+                        if name.is_empty() || names.iter().any(|x| x == name) {
+                            return Err(Error::InvalidCapture(name.to_owned()));
+                        }
+                        names.push(name.to_owned());
+
+                        ValuePattern::Capture(name.to_owned())
+                    }
+                    _ => ValuePattern::Exact(ava.clone()),
+                };
+                avas.push((ava.ty().normalize(), value));
+            }
+            avas.sort_by_key(|x| x.0);
+            rdns.push(avas);
+        }
+
+        Ok(Self { rdns })
+    }
+}
+
+/// The values captured when matching a [DnPattern].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DnCaptures {
+    captures: Vec<(String, String)>,
+}
+
+impl DnCaptures {
+    /// Get the value captured under the given name, as found in the DN.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.captures
+            .iter()
+            .find_map(|(x, value)| (x == name).then_some(value.as_str()))
+    }
+
+    /// Returns an iterator over all captured names and values, in the order
+    /// of the RDNs of the pattern.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.captures.iter().map(|(x, y)| (x.as_str(), y.as_str()))
+    }
+}
@@ -0,0 +1,117 @@
+//! LDAP string preparation, which transforms attribute values before they
+//! are compared:
+//! <https://datatracker.ietf.org/doc/html/rfc4518#section-2>.
+
+use stringprep::tables;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+use crate::{Error, Result};
+
+/// Prepare a value so it can be compared with other prepared values.
+pub(crate) fn prepare(value: &str, case_fold: bool) -> Result<String> {
+    // 1. Transcode. Already done, we only deal with Unicode strings.
+
+    // 2. Map. Some characters are removed, some are compared as if they were
+    // a simple space and case folding uses table B.2 of RFC3454 as it is
+    // meant to be followed by NFKC normalization.
+    let mapped = value
+        .chars()
+        .filter(|&c| !is_mapped_to_nothing(c))
+        .map(|c| if is_mapped_to_space(c) { ' ' } else { c });
+    let mapped = if case_fold {
+        mapped
+            .flat_map(tables::case_fold_for_nfkc)
+            .collect::<String>()
+    } else {
+        mapped.collect()
+    };
+
+    // 3. Normalize.
+    let normalized = mapped.nfkc().collect::<String>();
+
+    // 4. Prohibit. Strings may also not start with a combining character.
+    if let Some(c) = normalized.chars().find(|&c| is_prohibited(c)) {
+        return Err(Error::UnexpectedCharacter(c));
+    }
+    if let Some(c) = normalized.chars().next().filter(|&c| is_combining_mark(c)) {
+        return Err(Error::UnexpectedCharacter(c));
+    }
+
+    // 5. Check bidi. RFC4518 states that bidirectional characters are
+    // ignored, so there's nothing to check. The characters that change the
+    // display direction were already removed when mapping.
+
+    // 6. Insignificant character handling. RFC4518 has the prepared string
+    // start and end with a single space and have every inner run of spaces
+    // replaced by two spaces. For equality matching it's equivalent and
+    // simpler to trim the string and collapse inner runs into a single
+    // space.
+    let mut res = String::with_capacity(normalized.len());
+    for word in normalized.split(' ').filter(|x| !x.is_empty()) {
+        if !res.is_empty() {
+            res.push(' ');
+        }
+        res += word;
+    }
+
+    Ok(res)
+}
+
+// <https://datatracker.ietf.org/doc/html/rfc4518#section-2.2>
+fn is_mapped_to_nothing(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}'
+            | '\u{1806}'
+            | '\u{034F}'
+            | '\u{180B}'..='\u{180D}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{FFFC}'
+            | '\u{200B}'
+            // Control code points, other than the ones mapped to spaces
+            | '\u{0000}'..='\u{0008}'
+            | '\u{000E}'..='\u{001F}'
+            | '\u{007F}'..='\u{0084}'
+            | '\u{0086}'..='\u{009F}'
+            | '\u{06DD}'
+            | '\u{070F}'
+            | '\u{180E}'
+            | '\u{200C}'..='\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{2063}'
+            | '\u{206A}'..='\u{206F}'
+            | '\u{FEFF}'
+            | '\u{FFF9}'..='\u{FFFB}'
+            | '\u{1D173}'..='\u{1D17A}'
+            | '\u{E0001}'
+            | '\u{E0020}'..='\u{E007F}'
+    )
+}
+
+// <https://datatracker.ietf.org/doc/html/rfc4518#section-2.2>
+fn is_mapped_to_space(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0009}'..='\u{000D}'
+            | '\u{0085}'
+            // Separators
+            | '\u{0020}'
+            | '\u{00A0}'
+            | '\u{1680}'
+            | '\u{2000}'..='\u{200A}'
+            | '\u{2028}'..='\u{2029}'
+            | '\u{202F}'
+            | '\u{205F}'
+            | '\u{3000}'
+    )
+}
+
+// <https://datatracker.ietf.org/doc/html/rfc4518#section-2.4>
+fn is_prohibited(c: char) -> bool {
+    tables::unassigned_code_point(c)
+        || tables::private_use(c)
+        || tables::non_character_code_point(c)
+        || tables::surrogate_code(c)
+        || tables::change_display_properties_or_deprecated(c)
+        || c == '\u{FFFD}'
+}
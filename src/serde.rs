@@ -0,0 +1,160 @@
+//! Serialization and deserialization with [serde].
+//!
+//! [DistinguishedName]s are serialized as strings in the OpenFinance variant
+//! of the RFC4514 format, or in the structured format of [structured].
+//! [RdnType]s are serialized as their names, or as OIDs for types without a
+//! name, and [DnComparator]s as their canonical string, see
+//! [DnComparator::to_canonical_string].
+
+use std::{borrow::Cow, fmt, str::FromStr};
+
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    AttributeTypeAndValue, DistinguishedName, DnComparator, RdnType, RelativeDistinguishedName,
+};
+
+impl Serialize for RdnType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for RdnType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = Cow::<str>::deserialize(deserializer)?;
+
+        Self::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for DistinguishedName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_of_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for DistinguishedName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(DnVisitor)
+    }
+}
+
+struct DnVisitor;
+
+impl<'de> Visitor<'de> for DnVisitor {
+    type Value = DistinguishedName;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a distinguished name string or sequence of RDNs")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        DistinguishedName::from_str(v).map_err(E::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut rdns = Vec::new();
+        while let Some(rdn) = seq.next_element::<Rdn<'_>>()? {
+            let avas = rdn
+                .into_attributes()
+                .into_iter()
+                .map(|x| AttributeTypeAndValue::new(x.ty, x.value.into_owned()))
+                .collect();
+            let rdn = RelativeDistinguishedName::multi_valued(avas)
+                .ok_or_else(|| de::Error::custom(crate::Error::EmptyRdn))?;
+            rdns.push(rdn);
+        }
+        rdns.reverse();
+
+        Ok(DistinguishedName { rdns })
+    }
+}
+
+impl Serialize for DnComparator {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_canonical_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for DnComparator {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = Cow::<str>::deserialize(deserializer)?;
+
+        Self::from_canonical_string(&s).map_err(de::Error::custom)
+    }
+}
+
+/// (De)serialization of [DistinguishedName]s as sequences of RDNs in string
+/// order, for use with `#[serde(with = "of_dn_parser::serde::structured")]`.
+///
+/// Each RDN is a `{"type": ..., "value": ...}` object, except for
+/// multi-valued RDNs which are sequences of such objects, e.g.
+/// `[[{"type": "CN", "value": "app"}, {"type": "UID", "value": "1"}],
+/// {"type": "C", "value": "BR"}]`.
+pub mod structured {
+    use serde::{Deserializer, Serializer};
+
+    use super::{Attribute, DnVisitor, Rdn};
+    use crate::DistinguishedName;
+
+    /// Serialize a DN as a sequence of RDNs.
+    pub fn serialize<S: Serializer>(
+        dn: &DistinguishedName,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let rdns = dn.rdns.iter().rev().map(|rdn| {
+            Rdn::from_attributes(
+                rdn.iter()
+                    .map(|x| Attribute {
+                        ty: x.ty(),
+                        value: x.value().into(),
+                    })
+                    .collect(),
+            )
+        });
+
+        serializer.collect_seq(rdns)
+    }
+
+    /// Deserialize a DN from either a sequence of RDNs or a string.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DistinguishedName, D::Error> {
+        deserializer.deserialize_any(DnVisitor)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Attribute<'a> {
+    #[serde(rename = "type")]
+    ty: RdnType,
+    value: Cow<'a, str>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Rdn<'a> {
+    Single(Attribute<'a>),
+    Multi(Vec<Attribute<'a>>),
+}
+
+impl<'a> Rdn<'a> {
+    fn from_attributes(mut attributes: Vec<Attribute<'a>>) -> Self {
+        if attributes.len() == 1 {
+            Self::Single(attributes.remove(0))
+        } else {
+            Self::Multi(attributes)
+        }
+    }
+
+    fn into_attributes(self) -> Vec<Attribute<'a>> {
+        match self {
+            Self::Single(x) => vec![x],
+            Self::Multi(x) => x,
+        }
+    }
+}
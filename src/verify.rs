@@ -0,0 +1,177 @@
+//! Verification of the `tls_client_auth_subject_dn` registered through DCR
+//! against the client certificate presented during mTLS:
+//! <https://datatracker.ietf.org/doc/html/rfc8705#section-2.1.2>.
+
+use crate::{
+    DistinguishedName, Error, MatchingProfile, ParserOptions, RdnDiff, RelativeDistinguishedName,
+    Result,
+};
+
+/// Checks that the subject DN registered by a client matches the DN of the
+/// certificate it presents.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SubjectDnVerifier {
+    profile: MatchingProfile,
+    ignore_order: bool,
+    parser_options: ParserOptions,
+}
+
+impl SubjectDnVerifier {
+    /// Create a verifier using the standard matching rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a verifier using the given set of matching rules.
+    pub fn with_profile(profile: MatchingProfile) -> Self {
+        Self {
+            profile,
+            ..Self::default()
+        }
+    }
+
+    /// Whether to accept DNs with the same RDNs in a different order, see
+    /// [crate::UnorderedDnComparator]. Disabled by default.
+    pub fn ignore_order(mut self, ignore: bool) -> Self {
+        self.ignore_order = ignore;
+        self
+    }
+
+    /// Parse registered DNs with the given options, e.g. to limit the size
+    /// of untrusted input. Uses the default options unless set.
+    pub fn parser_options(mut self, options: ParserOptions) -> Self {
+        self.parser_options = options;
+        self
+    }
+
+    /// Verify a registered `tls_client_auth_subject_dn` against the subject
+    /// DN of the presented certificate.
+    pub fn verify(&self, registered: &str, presented: &DistinguishedName) -> Verification {
+        let registered =
+            DistinguishedName::parse_with(registered, &self.parser_options).and_then(|dn| {
+                let cmp = dn.comparator_with(self.profile)?;
+                Ok((dn, cmp))
+            });
+        let (registered, registered_cmp) = match registered {
+            Ok(x) => x,
+            Err(e) => return Verification::InvalidRegistered(e),
+        };
+        let presented_cmp = match presented.comparator_with(self.profile) {
+            Ok(x) => x,
+            Err(e) => return Verification::InvalidPresented(e),
+        };
+        let is_match = if self.ignore_order {
+            registered_cmp.clone().into_unordered() == presented_cmp.clone().into_unordered()
+        } else {
+            registered_cmp == presented_cmp
+        };
+        if is_match {
+            return Verification::Match;
+        }
+
+        // Align both DNs so that an extra or missing RDN doesn't shift the
+        // pairs after it
+        let rdn = |dn: &DistinguishedName, position: usize| {
+            Some(dn.rdns[dn.rdns.len() - 1 - position].clone())
+        };
+        let diffs = if self.ignore_order {
+            registered_cmp.diff_unordered(&presented_cmp)
+        } else {
+            registered_cmp.diff(&presented_cmp)
+        };
+        let mismatches = diffs
+            .into_iter()
+            .map(|diff| match diff {
+                RdnDiff::MissingOnLeft { position, .. } => RdnMismatch {
+                    registered: None,
+                    presented: rdn(presented, position),
+                },
+                RdnDiff::MissingOnRight { position, .. } => RdnMismatch {
+                    registered: rdn(&registered, position),
+                    presented: None,
+                },
+                RdnDiff::ValueDiffers {
+                    left_position,
+                    right_position,
+                    ..
+                }
+                | RdnDiff::OrderDiffers {
+                    left_position,
+                    right_position,
+                    ..
+                } => RdnMismatch {
+                    registered: rdn(&registered, left_position),
+                    presented: rdn(presented, right_position),
+                },
+            })
+            .collect();
+
+        Verification::Mismatch(mismatches)
+    }
+
+    /// Verify a registered `tls_client_auth_subject_dn` against the subject
+    /// DN of a DER or PEM encoded certificate.
+    #[cfg(feature = "x509")]
+    pub fn verify_certificate(&self, registered: &str, certificate: &[u8]) -> Verification {
+        match DistinguishedName::subject_from_certificate(certificate) {
+            Ok(presented) => self.verify(registered, &presented),
+            Err(e) => Verification::InvalidPresented(e),
+        }
+    }
+}
+
+/// The outcome of a [SubjectDnVerifier] check.
+#[derive(Debug)]
+pub enum Verification {
+    /// Both DNs match.
+    Match,
+    /// The DNs don't match. Holds every difference between them, in the
+    /// order of the registered DN.
+    Mismatch(Vec<RdnMismatch>),
+    /// The registered DN could not be parsed or compared.
+    InvalidRegistered(Error),
+    /// The presented DN could not be parsed or compared.
+    InvalidPresented(Error),
+}
+
+impl Verification {
+    /// Whether both DNs match.
+    pub fn is_match(&self) -> bool {
+        matches!(self, Self::Match)
+    }
+
+    /// Convert into a [Result] with whether both DNs match, discarding the
+    /// details of a mismatch.
+    pub fn into_result(self) -> Result<bool> {
+        match self {
+            Self::Match => Ok(true),
+            Self::Mismatch(_) => Ok(false),
+            Self::InvalidRegistered(e) | Self::InvalidPresented(e) => Err(e),
+        }
+    }
+}
+
+/// A difference between the registered and presented DNs, as explained by
+/// [crate::DnComparator::diff].
+///
+/// This is either an RDN missing from one of the DNs, a pair of RDNs with the
+/// same attribute types but different values, or the same RDN found in
+/// different positions. The latter is never reported when ignoring the order
+/// of RDNs.
+#[derive(Clone, Debug)]
+pub struct RdnMismatch {
+    registered: Option<RelativeDistinguishedName>,
+    presented: Option<RelativeDistinguishedName>,
+}
+
+impl RdnMismatch {
+    /// Get the RDN of the registered DN, unless it is missing from it.
+    pub fn registered(&self) -> Option<&RelativeDistinguishedName> {
+        self.registered.as_ref()
+    }
+
+    /// Get the RDN of the presented DN, unless it is missing from it.
+    pub fn presented(&self) -> Option<&RelativeDistinguishedName> {
+        self.presented.as_ref()
+    }
+}
@@ -0,0 +1,85 @@
+//! Extraction of distinguished names from X.509 certificates.
+
+use std::borrow::Cow;
+
+use crate::{
+    der::{Reader, TAG_SEQUENCE},
+    DistinguishedName, Error, Result,
+};
+
+// Tag of the explicit `[0] version` field
+const TAG_VERSION: u8 = 0xa0;
+const TAG_INTEGER: u8 = 0x02;
+
+impl DistinguishedName {
+    /// Parse the subject DN of an X.509 certificate. The certificate may be
+    /// either DER or PEM encoded.
+    pub fn subject_from_certificate(certificate: &[u8]) -> Result<Self> {
+        let certificate = decode_certificate(certificate)?;
+        let (_, subject) = find_names(&certificate)?;
+
+        Self::from_der(subject)
+    }
+
+    /// Parse the issuer DN of an X.509 certificate. The certificate may be
+    /// either DER or PEM encoded.
+    pub fn issuer_from_certificate(certificate: &[u8]) -> Result<Self> {
+        let certificate = decode_certificate(certificate)?;
+        let (issuer, _) = find_names(&certificate)?;
+
+        Self::from_der(issuer)
+    }
+}
+
+// Get the DER encoding of a certificate that may be PEM encoded. When given a
+// PEM bundle, only the first certificate is considered.
+fn decode_certificate(certificate: &[u8]) -> Result<Cow<'_, [u8]>> {
+    if !certificate.trim_ascii_start().starts_with(b"-----BEGIN") {
+        return Ok(certificate.into());
+    }
+
+    let pem = pem::parse(certificate)?;
+    if pem.tag() != "CERTIFICATE" {
+        return Err(Error::UnexpectedPemLabel(pem.tag().to_owned()));
+    }
+
+    Ok(pem.into_contents().into())
+}
+
+// Walk the certificate just far enough to find the DER encoding of the
+// issuer and subject names:
+//
+// Certificate ::= SEQUENCE {
+//     tbsCertificate       TBSCertificate,
+//     ... }
+//
+// TBSCertificate ::= SEQUENCE {
+//     version         [0]  EXPLICIT Version DEFAULT v1,
+//     serialNumber         CertificateSerialNumber,
+//     signature            AlgorithmIdentifier,
+//     issuer               Name,
+//     validity             Validity,
+//     subject              Name,
+//     ... }
+//
+// <https://datatracker.ietf.org/doc/html/rfc5280#section-4.1>
+fn find_names(certificate: &[u8]) -> Result<(&[u8], &[u8])> {
+    let mut reader = Reader::new(certificate);
+    let mut certificate = Reader::new(reader.read_expected(TAG_SEQUENCE)?);
+    reader.finish()?;
+
+    let mut tbs_certificate = Reader::new(certificate.read_expected(TAG_SEQUENCE)?);
+    let (mut tag, _) = tbs_certificate.read()?;
+    if tag == TAG_VERSION {
+        (tag, _) = tbs_certificate.read()?;
+    }
+    if tag != TAG_INTEGER {
+        return Err(Error::UnexpectedTag(tag));
+    }
+    tbs_certificate.read_expected(TAG_SEQUENCE)?;
+    let issuer = tbs_certificate.read_raw(TAG_SEQUENCE)?;
+    tbs_certificate.read_expected(TAG_SEQUENCE)?;
+    let subject = tbs_certificate.read_raw(TAG_SEQUENCE)?;
+
+    Ok((issuer, subject))
+}